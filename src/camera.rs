@@ -9,6 +9,12 @@ pub struct Camera {
     azimuth: f32,
 }
 
+impl Default for Camera {
+    fn default() -> Camera {
+        Camera::new()
+    }
+}
+
 impl Camera {
     pub fn new() -> Camera {
         let azimuth = 90.0_f32.to_radians();
@@ -25,6 +31,7 @@ impl Camera {
         self.azimuth += delta;
     }
 
+    #[allow(clippy::manual_clamp)]
     pub fn rotate_polar(&mut self, delta: f32) {
         self.polar += delta;
 
@@ -0,0 +1,550 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::cube::{self, Face};
+use crate::syntax::{ConcreteSyntax, ConcreteSyntax::*, Ident, SyntaxRec, SyntaxRef};
+use crate::termbuilder::Term;
+
+/// A point of the interval.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Dim {
+    Zero,
+    One,
+    Var(Ident),
+}
+
+/// An assignment of dimension variables to points of the interval.
+/// Variables that are not mentioned are left alone.
+pub type Env = HashMap<Ident, Dim>;
+
+#[derive(Clone, Debug)]
+pub enum CofError {
+    NotACofibration(SyntaxRef),
+    NotADimension(SyntaxRef),
+    ExpectedSplit(SyntaxRef),
+    NotCellular(SyntaxRef),
+}
+
+pub fn eval_dim(term: &Term, sref: SyntaxRef, env: &Env) -> Result<Dim, CofError> {
+    match &term.map[sref] {
+        Lit(0) => Ok(Dim::Zero),
+        Lit(1) => Ok(Dim::One),
+        Var(id) => Ok(env.get(id).cloned().unwrap_or_else(|| Dim::Var(id.clone()))),
+        _ => Err(CofError::NotADimension(sref)),
+    }
+}
+
+pub fn eval_eq(r: &Dim, s: &Dim) -> Option<bool> {
+    match (r, s) {
+        (Dim::Zero, Dim::Zero) | (Dim::One, Dim::One) => Some(true),
+        (Dim::Zero, Dim::One) | (Dim::One, Dim::Zero) => Some(false),
+        (Dim::Var(x), Dim::Var(y)) if x == y => Some(true),
+        _ => None,
+    }
+}
+
+pub fn eval_le(r: &Dim, s: &Dim) -> Option<bool> {
+    match (r, s) {
+        (Dim::Zero, _) | (_, Dim::One) => Some(true),
+        (Dim::One, Dim::Zero) => Some(false),
+        (Dim::Var(x), Dim::Var(y)) if x == y => Some(true),
+        _ => None,
+    }
+}
+
+/// Decide a cofibration under an assignment of its dimension variables.
+///
+/// Cofibrations that mention unassigned variables may not have a definite value,
+/// in which case we return 'None'. Joins and meets follow Kleene's three-valued logic,
+/// so 'i = 0 \/ #t' is still true even if we know nothing about 'i'.
+pub fn eval(term: &Term, sref: SyntaxRef, env: &Env) -> Result<Option<bool>, CofError> {
+    match &term.map[sref] {
+        TopC => Ok(Some(true)),
+        BotC => Ok(Some(false)),
+        CofEq(sref1, sref2) => Ok(eval_eq(
+            &eval_dim(term, *sref1, env)?,
+            &eval_dim(term, *sref2, env)?,
+        )),
+        CofLe(sref1, sref2) => Ok(eval_le(
+            &eval_dim(term, *sref1, env)?,
+            &eval_dim(term, *sref2, env)?,
+        )),
+        Join(vsref) => {
+            let mut result = Some(false);
+            for sref in vsref {
+                match eval(term, *sref, env)? {
+                    Some(true) => result = Some(true),
+                    None if result == Some(false) => result = None,
+                    _ => (),
+                }
+            }
+            Ok(result)
+        }
+        Meet(vsref) => {
+            let mut result = Some(true);
+            for sref in vsref {
+                match eval(term, *sref, env)? {
+                    Some(false) => result = Some(false),
+                    None if result == Some(true) => result = None,
+                    _ => (),
+                }
+            }
+            Ok(result)
+        }
+        _ => Err(CofError::NotACofibration(sref)),
+    }
+}
+
+/// The assignment of dimensions that a cell of the cube holds fixed.
+pub fn cell_env(dims: &[(String, bool)]) -> Env {
+    dims.iter()
+        .map(|(nm, d)| {
+            let id = Ident::User(vec![nm.clone()]);
+            (id, if *d { Dim::One } else { Dim::Zero })
+        })
+        .collect()
+}
+
+/// Does a cofibration hold everywhere on a face of the cube?
+pub fn holds_on(term: &Term, sref: SyntaxRef, face: &Face) -> Result<bool, CofError> {
+    Ok(eval(term, sref, &cell_env(&face.dims))? == Some(true))
+}
+
+/// For each branch of a 'CofSplit', the faces of the cube that the branch applies to.
+pub fn branch_faces<'a>(
+    term: &Term,
+    split: SyntaxRef,
+    faces: &'a [Face],
+) -> Result<Vec<Vec<&'a Face>>, CofError> {
+    let branches = match &term.map[split] {
+        CofSplit(branches) => branches,
+        _ => return Err(CofError::ExpectedSplit(split)),
+    };
+    branches
+        .iter()
+        .map(|(cof, _)| cube::faces_in(faces, term, *cof))
+        .collect()
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Atom {
+    Eq(Dim, Dim),
+    Le(Dim, Dim),
+}
+
+/// A cofibration in disjunctive normal form: a join of meets of atoms.
+///
+/// Normal forms produced by 'normalize' are canonical: every clause is satisfiable, has had
+/// its equations solved (including the ones that follow from a loop of '<='), keeps only the
+/// inequalities that don't follow from the others, and is not implied by another clause.
+/// Everything is sorted.
+/// The empty join is '#f', and a join containing the empty meet is '#t'.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Dnf(pub Vec<Vec<Atom>>);
+
+impl Dim {
+    pub fn to_syntax(&self) -> ConcreteSyntax<SyntaxRec> {
+        match self {
+            Dim::Zero => Lit(0),
+            Dim::One => Lit(1),
+            Dim::Var(id) => Var(id.clone()),
+        }
+    }
+}
+
+impl Atom {
+    pub fn to_syntax(&self) -> ConcreteSyntax<SyntaxRec> {
+        match self {
+            Atom::Eq(r, s) => CofEq(SyntaxRec::new(r.to_syntax()), SyntaxRec::new(s.to_syntax())),
+            Atom::Le(r, s) => CofLe(SyntaxRec::new(r.to_syntax()), SyntaxRec::new(s.to_syntax())),
+        }
+    }
+}
+
+// The result of simplifying 'r <= s' once both sides have been solved for.
+enum LeResult {
+    True,
+    False,
+    Eq(Dim, Dim),
+    Le(Dim, Dim),
+}
+
+fn simplify_le(r: Dim, s: Dim) -> LeResult {
+    match (r, s) {
+        (Dim::Zero, _) | (_, Dim::One) => LeResult::True,
+        (Dim::One, Dim::Zero) => LeResult::False,
+        (r, s) if r == s => LeResult::True,
+        (Dim::One, s) => LeResult::Eq(s, Dim::One),
+        (r, Dim::Zero) => LeResult::Eq(r, Dim::Zero),
+        (r, s) => LeResult::Le(r, s),
+    }
+}
+
+/// Solve the equations of a conjunction of atoms, returning 'None' if it is unsatisfiable.
+fn canonical_clause(atoms: &[Atom]) -> Option<Vec<Atom>> {
+    // Every solved variable points directly at its representative: an endpoint if the
+    // variable's class has one, otherwise the least variable in the class.
+    let mut solved: HashMap<Ident, Dim> = HashMap::new();
+    let resolve = |solved: &HashMap<Ident, Dim>, d: &Dim| match d {
+        Dim::Var(x) => solved.get(x).cloned().unwrap_or_else(|| d.clone()),
+        _ => d.clone(),
+    };
+
+    let mut eqs = Vec::new();
+    let mut les = Vec::new();
+    for atom in atoms {
+        match atom {
+            Atom::Eq(r, s) => eqs.push((r.clone(), s.clone())),
+            Atom::Le(r, s) => les.push((r.clone(), s.clone())),
+        }
+    }
+
+    loop {
+        while let Some((r, s)) = eqs.pop() {
+            let (r, s) = (resolve(&solved, &r), resolve(&solved, &s));
+            let (x, rep) = match (r, s) {
+                (r, s) if r == s => continue,
+                (Dim::Var(x), Dim::Var(y)) => {
+                    if x < y {
+                        (y, Dim::Var(x))
+                    } else {
+                        (x, Dim::Var(y))
+                    }
+                }
+                (Dim::Var(x), s) => (x, s),
+                (r, Dim::Var(y)) => (y, r),
+                _ => return None,
+            };
+            for d in solved.values_mut() {
+                if *d == Dim::Var(x.clone()) {
+                    *d = rep.clone();
+                }
+            }
+            solved.insert(x, rep);
+        }
+
+        let mut remaining = Vec::new();
+        for (r, s) in les {
+            match simplify_le(resolve(&solved, &r), resolve(&solved, &s)) {
+                LeResult::True => (),
+                LeResult::False => return None,
+                LeResult::Eq(r, s) => eqs.push((r, s)),
+                LeResult::Le(r, s) => remaining.push((r, s)),
+            }
+        }
+        // Every variable on a loop 'r <= s <= ... <= r' is equal to the others.
+        for (r, s) in &remaining {
+            if reaches(&remaining, s, r) {
+                eqs.push((r.clone(), s.clone()));
+            }
+        }
+        les = remaining;
+
+        if eqs.is_empty() {
+            break;
+        }
+    }
+
+    // Without loops, the inequalities that don't follow from the others are determined by the
+    // order alone, so keeping just those makes the clause canonical.
+    les.sort();
+    les.dedup();
+    let implied: Vec<bool> = (0..les.len())
+        .map(|i| {
+            let mut others = les.clone();
+            let (r, s) = others.remove(i);
+            reaches(&others, &r, &s)
+        })
+        .collect();
+    let les = les
+        .into_iter()
+        .zip(implied)
+        .filter_map(|(le, implied)| (!implied).then_some(le));
+
+    let mut clause: Vec<Atom> = solved
+        .into_iter()
+        .map(|(x, d)| Atom::Eq(Dim::Var(x), d))
+        .chain(les.map(|(r, s)| Atom::Le(r, s)))
+        .collect();
+    clause.sort();
+    clause.dedup();
+    Some(clause)
+}
+
+// Is there a chain 'from <= ... <= to' among the inequalities?
+fn reaches(les: &[(Dim, Dim)], from: &Dim, to: &Dim) -> bool {
+    let mut seen = vec![from.clone()];
+    let mut todo = vec![from.clone()];
+    while let Some(d) = todo.pop() {
+        if d == *to {
+            return true;
+        }
+        for (r, s) in les {
+            if *r == d && !seen.contains(s) {
+                seen.push(s.clone());
+                todo.push(s.clone());
+            }
+        }
+    }
+    false
+}
+
+// Does the first clause imply the second? Both must already be canonical.
+fn clause_entails(c: &[Atom], d: &[Atom]) -> bool {
+    let mut both = c.to_vec();
+    both.extend_from_slice(d);
+    canonical_clause(&both).as_deref() == Some(c)
+}
+
+impl Dnf {
+    fn top() -> Dnf {
+        Dnf(vec![vec![]])
+    }
+
+    fn bot() -> Dnf {
+        Dnf(vec![])
+    }
+
+    fn atom(atom: Atom) -> Dnf {
+        Dnf(vec![vec![atom]])
+    }
+
+    fn join(dnfs: Vec<Dnf>) -> Dnf {
+        Dnf(dnfs.into_iter().flat_map(|Dnf(clauses)| clauses).collect())
+    }
+
+    fn meet(dnfs: Vec<Dnf>) -> Dnf {
+        dnfs.into_iter().fold(Dnf::top(), |Dnf(acc), Dnf(clauses)| {
+            let mut product = Vec::with_capacity(acc.len() * clauses.len());
+            for c in &acc {
+                for d in &clauses {
+                    let mut cd = c.clone();
+                    cd.extend_from_slice(d);
+                    product.push(cd);
+                }
+            }
+            Dnf(product)
+        })
+    }
+
+    // Canonicalize every clause, then drop the unsatisfiable and the redundant ones.
+    fn simplify(self) -> Dnf {
+        let Dnf(clauses) = self;
+        let mut clauses: Vec<Vec<Atom>> = clauses
+            .iter()
+            .filter_map(|clause| canonical_clause(clause))
+            .collect();
+        clauses.sort();
+        clauses.dedup();
+
+        let absorbed: Vec<bool> = clauses
+            .iter()
+            .enumerate()
+            .map(|(i, c)| {
+                clauses
+                    .iter()
+                    .enumerate()
+                    .any(|(j, d)| i != j && clause_entails(c, d))
+            })
+            .collect();
+        Dnf(clauses
+            .into_iter()
+            .zip(absorbed)
+            .filter_map(|(c, absorbed)| if absorbed { None } else { Some(c) })
+            .collect())
+    }
+
+    pub fn is_top(&self) -> bool {
+        self.0.iter().any(|clause| clause.is_empty())
+    }
+
+    pub fn is_bot(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Does this cofibration imply the other one?
+    ///
+    /// This checks that each clause implies some clause of the other cofibration,
+    /// which is sound, but can miss entailments that need a case split,
+    /// such as '#t' implying 'i <= j \/ j <= i'.
+    pub fn entails(&self, other: &Dnf) -> bool {
+        self.0
+            .iter()
+            .all(|c| other.0.iter().any(|d| clause_entails(c, d)))
+    }
+
+    pub fn equivalent(&self, other: &Dnf) -> bool {
+        self.entails(other) && other.entails(self)
+    }
+
+    pub fn to_syntax(&self) -> ConcreteSyntax<SyntaxRec> {
+        let clause_syntax = |clause: &Vec<Atom>| match clause.as_slice() {
+            [] => TopC,
+            [atom] => atom.to_syntax(),
+            atoms => Meet(
+                atoms
+                    .iter()
+                    .map(|atom| SyntaxRec::new(atom.to_syntax()))
+                    .collect(),
+            ),
+        };
+        match self.0.as_slice() {
+            [] => BotC,
+            [clause] => clause_syntax(clause),
+            clauses => Join(
+                clauses
+                    .iter()
+                    .map(|clause| SyntaxRec::new(clause_syntax(clause)))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+fn to_dnf(term: &Term, sref: SyntaxRef) -> Result<Dnf, CofError> {
+    let env = Env::new();
+    match &term.map[sref] {
+        TopC => Ok(Dnf::top()),
+        BotC => Ok(Dnf::bot()),
+        CofEq(sref1, sref2) => Ok(Dnf::atom(Atom::Eq(
+            eval_dim(term, *sref1, &env)?,
+            eval_dim(term, *sref2, &env)?,
+        ))),
+        CofLe(sref1, sref2) => Ok(Dnf::atom(Atom::Le(
+            eval_dim(term, *sref1, &env)?,
+            eval_dim(term, *sref2, &env)?,
+        ))),
+        Join(vsref) => Ok(Dnf::join(
+            vsref
+                .iter()
+                .map(|sref| to_dnf(term, *sref))
+                .collect::<Result<_, _>>()?,
+        )),
+        Meet(vsref) => Ok(Dnf::meet(
+            vsref
+                .iter()
+                .map(|sref| to_dnf(term, *sref))
+                .collect::<Result<_, _>>()?,
+        )),
+        _ => Err(CofError::NotACofibration(sref)),
+    }
+}
+
+/// Put a cofibration into canonical disjunctive normal form.
+pub fn normalize(term: &Term, sref: SyntaxRef) -> Result<Dnf, CofError> {
+    let dnf = to_dnf(term, sref)?.simplify();
+    if dnf.is_top() {
+        Ok(Dnf::top())
+    } else {
+        Ok(dnf)
+    }
+}
+
+impl fmt::Display for Dim {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Dim::Zero => write!(f, "0"),
+            Dim::One => write!(f, "1"),
+            Dim::Var(id) => write!(f, "{}", id),
+        }
+    }
+}
+
+impl fmt::Display for Atom {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Atom::Eq(r, s) => write!(f, "{} = {}", r, s),
+            Atom::Le(r, s) => write!(f, "{} <= {}", r, s),
+        }
+    }
+}
+
+impl fmt::Display for Dnf {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_bot() {
+            return write!(f, "#f");
+        }
+        for (i, clause) in self.0.iter().enumerate() {
+            if i > 0 {
+                write!(f, " \\/ ")?;
+            }
+            if clause.is_empty() {
+                write!(f, "#t")?;
+            }
+            for (j, atom) in clause.iter().enumerate() {
+                if j > 0 {
+                    write!(f, " /\\ ")?;
+                }
+                write!(f, "{}", atom)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::syntax::cs;
+
+    fn normal(cs: ConcreteSyntax<SyntaxRec>) -> Dnf {
+        let (term, sref) = Term::from_syntax(cs);
+        normalize(&term, sref).expect("Not a cofibration")
+    }
+
+    fn var(name: &str) -> Dim {
+        Dim::Var(Ident::User(vec![name.to_string()]))
+    }
+
+    #[test]
+    fn equations_are_symmetric() {
+        assert_eq!(normal(cs!(i = j)), normal(cs!(j = i)));
+        assert_eq!(
+            normal(cs!(i = j)),
+            Dnf(vec![vec![Atom::Eq(var("j"), var("i"))]])
+        );
+        assert!(normal(cs!(i = j)).equivalent(&normal(cs!(j = i))));
+        assert_eq!(normal(cs!(0 = i)), normal(cs!(i = 0)));
+        assert_eq!(
+            normal(cs!(meet((i = j), (j = k)))),
+            normal(cs!(meet((k = j), (i = k))))
+        );
+    }
+
+    #[test]
+    fn trivial_equations() {
+        assert!(normal(cs!(i = i)).is_top());
+        assert!(normal(cs!(0 = 1)).is_bot());
+        assert!(normal(cs!(1 = 1)).is_top());
+        assert!(normal(cs!(meet((i = 0), (i = 1)))).is_bot());
+        assert!(normal(cs!(meet((i = j), (j = 0), (i = 1)))).is_bot());
+    }
+
+    #[test]
+    fn inequalities() {
+        assert_eq!(
+            normal(cs!(meet((i <= j), (j <= k), (k <= i)))),
+            normal(cs!(meet((i = j), (j = k))))
+        );
+        assert!(normal(cs!(meet((i <= j), (j <= k), (k <= i))))
+            .equivalent(&normal(cs!(meet((k = j), (i = k))))));
+        assert_eq!(
+            normal(cs!(meet((i <= j), (j <= k), (i <= k)))),
+            normal(cs!(meet((i <= j), (j <= k))))
+        );
+        assert!(normal(cs!(meet((i <= j), (j <= k)))).entails(&normal(cs!(i <= k))));
+    }
+
+    #[test]
+    fn absorption() {
+        assert_eq!(
+            normal(cs!(join((i = 0), (meet((i = 0), (j = 1)))))),
+            normal(cs!(i = 0))
+        );
+        assert_eq!(
+            normal(cs!(join((meet((j = i), (k = 0))), (i = j)))),
+            normal(cs!(i = j))
+        );
+        assert!(normal(cs!(join((i = 0), (i = 1)))).entails(&normal(cs!(join((i = 1), (i = 0))))));
+    }
+}
@@ -0,0 +1,127 @@
+use crate::cof::{self, CofError, Dim, Env};
+use crate::cube::{self, Face};
+use crate::syntax::{ConcreteSyntax, ConcreteSyntax::*, Ident, SyntaxRec, SyntaxRef};
+use crate::termbuilder::Term;
+
+/// A codimension 1 face of the cube, ie. the place where a single dimension is 0 or 1.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BoundaryFace {
+    pub dim: String,
+    pub side: bool,
+}
+
+impl BoundaryFace {
+    /// The cofibration 'dim = side' that carves out this face.
+    pub fn cofibration(&self) -> ConcreteSyntax<SyntaxRec> {
+        cube::cell_cofibration(&self.face_dims())
+    }
+
+    /// The fixed dimensions of this face, in the same format as 'cube::Face::dims'.
+    pub fn face_dims(&self) -> Vec<(String, bool)> {
+        vec![(self.dim.clone(), self.side)]
+    }
+
+    /// Does a rendered face of the cube lie on this boundary face?
+    pub fn contains(&self, face: &Face) -> bool {
+        face.dims
+            .iter()
+            .any(|(nm, d)| *nm == self.dim && *d == self.side)
+    }
+
+    fn env(&self) -> Env {
+        let d = if self.side { Dim::One } else { Dim::Zero };
+        Env::from([(Ident::User(vec![self.dim.clone()]), d)])
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Coverage {
+    /// Boundary faces that no branch of the split applies to.
+    pub uncovered: Vec<BoundaryFace>,
+    /// Boundary faces that more than one branch applies to, along with the indices of those branches.
+    pub overlapping: Vec<(BoundaryFace, Vec<usize>)>,
+}
+
+impl Coverage {
+    pub fn is_complete(&self) -> bool {
+        self.uncovered.is_empty()
+    }
+}
+
+/// Work out which boundary faces of the cube spanned by 'dims' are covered by the branches
+/// of a 'CofSplit'.
+///
+/// A branch only covers a face if its cofibration holds on the entire face. Any other
+/// dimensions mentioned by the branches, like the bound variable of an 'hcom' tube,
+/// are left free.
+pub fn check_split(term: &Term, split: SyntaxRef, dims: &[String]) -> Result<Coverage, CofError> {
+    let branches = match &term.map[split] {
+        CofSplit(branches) => branches,
+        _ => return Err(CofError::ExpectedSplit(split)),
+    };
+
+    let mut coverage = Coverage::default();
+    for dim in dims {
+        for side in [false, true] {
+            let face = BoundaryFace {
+                dim: dim.clone(),
+                side,
+            };
+            let env = face.env();
+
+            let mut covering = Vec::new();
+            for (i, (cof, _)) in branches.iter().enumerate() {
+                if cof::eval(term, *cof, &env)? == Some(true) {
+                    covering.push(i);
+                }
+            }
+
+            match covering.len() {
+                0 => coverage.uncovered.push(face),
+                1 => (),
+                _ => coverage.overlapping.push((face, covering)),
+            }
+        }
+    }
+    Ok(coverage)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::syntax::cs;
+
+    fn check(cs: ConcreteSyntax<SyntaxRec>) -> Result<Coverage, CofError> {
+        let (term, sref) = Term::from_syntax(cs);
+        check_split(&term, sref, &["i".to_string(), "j".to_string()])
+    }
+
+    fn face(dim: &str, side: bool) -> BoundaryFace {
+        BoundaryFace {
+            dim: dim.to_string(),
+            side,
+        }
+    }
+
+    #[test]
+    fn uncovered_faces() {
+        let coverage =
+            check(cs!(split { i = 0 => a, i = 1 => b, j = 0 => c })).expect("Not a split");
+        assert_eq!(coverage.uncovered, vec![face("j", true)]);
+        assert!(coverage.overlapping.is_empty());
+        assert!(!coverage.is_complete());
+    }
+
+    #[test]
+    fn overlapping_branches() {
+        let coverage =
+            check(cs!(split { join((i = 0), (j = 0)) => a, i = 0 => b })).expect("Not a split");
+        assert_eq!(coverage.overlapping, vec![(face("i", false), vec![0, 1])]);
+        assert_eq!(coverage.uncovered, vec![face("i", true), face("j", true)]);
+    }
+
+    #[test]
+    fn not_a_split() {
+        assert!(matches!(check(cs!(f a)), Err(CofError::ExpectedSplit(_))));
+    }
+}
@@ -2,7 +2,10 @@ use glium::*;
 use nalgebra::{Matrix4, Point3, Similarity3, Vector3};
 use ordered_float::NotNan;
 
+use crate::cof::{self, Atom, CofError, Dim, Dnf};
 use crate::linalg;
+use crate::syntax::{ConcreteSyntax, ConcreteSyntax::*, Ident, SyntaxRec, SyntaxRef};
+use crate::termbuilder::Term;
 use crate::vertex::Vertex;
 // Insert a zero bit at 'ix', shifting over the upper bits to compensate.
 fn insert_bit(bits: u32, ix: u32) -> u32 {
@@ -34,6 +37,71 @@ fn dims_from_point(dim_names: &[String], point: &[f32], d0: u32, d1: u32) -> Vec
     dims
 }
 
+/// The cofibration carving out a cell of the cube, given the dimensions the cell holds fixed.
+///
+/// This is the meet of an equation for each fixed dimension, so the cube itself is '#t'.
+pub fn cell_cofibration(dims: &[(String, bool)]) -> ConcreteSyntax<SyntaxRec> {
+    let mut eqs: Vec<SyntaxRec> = dims
+        .iter()
+        .map(|(nm, d)| {
+            SyntaxRec::new(CofEq(
+                SyntaxRec::new(Var(Ident::User(vec![nm.clone()]))),
+                SyntaxRec::new(Lit(*d as u32)),
+            ))
+        })
+        .collect();
+    if eqs.len() == 1 {
+        *eqs.pop().unwrap().value
+    } else {
+        Meet(eqs)
+    }
+}
+
+/// The cells of the cube that a cofibration carves out, as lists of fixed dimensions.
+///
+/// This only works for cofibrations that are (equivalent to) a join of meets of equations
+/// between dimensions and endpoints: something like 'i = j' picks out a diagonal, which is
+/// not made up of cells.
+pub fn cofibration_cells(
+    term: &Term,
+    sref: SyntaxRef,
+) -> Result<Vec<Vec<(String, bool)>>, CofError> {
+    let Dnf(clauses) = cof::normalize(term, sref)?;
+    clauses
+        .iter()
+        .map(|clause| {
+            clause
+                .iter()
+                .map(|atom| match atom {
+                    Atom::Eq(Dim::Var(Ident::User(parts)), Dim::Zero) if parts.len() == 1 => {
+                        Ok((parts[0].clone(), false))
+                    }
+                    Atom::Eq(Dim::Var(Ident::User(parts)), Dim::One) if parts.len() == 1 => {
+                        Ok((parts[0].clone(), true))
+                    }
+                    _ => Err(CofError::NotCellular(sref)),
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// The faces that lie entirely within a cofibration, eg. the faces of the cube that a branch
+/// of a split applies to.
+pub fn faces_in<'a>(
+    faces: &'a [Face],
+    term: &Term,
+    sref: SyntaxRef,
+) -> Result<Vec<&'a Face>, CofError> {
+    let mut inside = Vec::new();
+    for face in faces {
+        if cof::holds_on(term, sref, face)? {
+            inside.push(face);
+        }
+    }
+    Ok(inside)
+}
+
 #[derive(Clone, Debug)]
 pub struct Face {
     pub points: [Vector3<f32>; 4],
@@ -42,6 +110,10 @@ pub struct Face {
 }
 
 impl Face {
+    pub fn cofibration(&self) -> ConcreteSyntax<SyntaxRec> {
+        cell_cofibration(&self.dims)
+    }
+
     fn inside_out(v0: &Vector3<f32>, v1: &Vector3<f32>, q: &Point3<f32>, n: &Vector3<f32>) -> bool {
         (v1 - v0).cross(&(q.coords - v0)).dot(n) >= 0.0
     }
@@ -264,3 +336,80 @@ impl Cube {
             .unwrap();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::syntax::cs;
+
+    fn face(dims: &[(&str, bool)]) -> Face {
+        Face {
+            points: [Vector3::zeros(); 4],
+            normal: Vector3::zeros(),
+            dims: dims.iter().map(|(nm, d)| (nm.to_string(), *d)).collect(),
+        }
+    }
+
+    // The 2-faces of the 4-cube spanned by i, j, k and l where i and j are held fixed.
+    fn faces() -> Vec<Face> {
+        let mut faces = Vec::new();
+        for i in [false, true] {
+            for j in [false, true] {
+                faces.push(face(&[("i", i), ("j", j)]));
+            }
+        }
+        faces.push(face(&[("k", false), ("l", true)]));
+        faces
+    }
+
+    fn cells(cs: ConcreteSyntax<SyntaxRec>) -> Result<Vec<Vec<(String, bool)>>, CofError> {
+        let (term, sref) = Term::from_syntax(cs);
+        cofibration_cells(&term, sref)
+    }
+
+    fn dims_in(cs: ConcreteSyntax<SyntaxRec>) -> Vec<Vec<(String, bool)>> {
+        let (term, sref) = Term::from_syntax(cs);
+        let faces = faces();
+        faces_in(&faces, &term, sref)
+            .expect("Not a cofibration")
+            .into_iter()
+            .map(|face| face.dims.clone())
+            .collect()
+    }
+
+    #[test]
+    fn cells_to_cofibrations_and_back() {
+        for face in faces() {
+            assert_eq!(cells(face.cofibration()).unwrap(), vec![face.dims.clone()]);
+            assert_eq!(dims_in(face.cofibration()), vec![face.dims.clone()]);
+        }
+        assert_eq!(cells(cell_cofibration(&[])).unwrap(), vec![Vec::new()]);
+    }
+
+    #[test]
+    fn cofibrations_to_cells() {
+        assert_eq!(
+            cells(cs!(join((i = 0), (meet((j = 1), (k = 0)))))).unwrap(),
+            vec![
+                vec![("i".to_string(), false)],
+                vec![("j".to_string(), true), ("k".to_string(), false)],
+            ]
+        );
+        assert_eq!(cells(cs!(bot)).unwrap(), Vec::<Vec<_>>::new());
+        assert!(matches!(cells(cs!(i = j)), Err(CofError::NotCellular(_))));
+        assert!(matches!(cells(cs!(i <= j)), Err(CofError::NotCellular(_))));
+    }
+
+    #[test]
+    fn cofibrations_to_faces() {
+        let i0 = |j| vec![("i".to_string(), false), ("j".to_string(), j)];
+        assert_eq!(dims_in(cs!(i = 0)), vec![i0(false), i0(true)]);
+        assert_eq!(dims_in(cs!(meet((i = 0), (j = 1)))), vec![i0(true)]);
+        assert_eq!(dims_in(cs!(join((k = 1), (l = 0)))), Vec::<Vec<_>>::new());
+        assert_eq!(dims_in(cs!(meet((k = 0), top))).len(), 1);
+        assert_eq!(dims_in(cs!(top)).len(), 5);
+        assert_eq!(dims_in(cs!(i = j)).len(), 2);
+        // The line of a composition is free, so its cap holds on no face of the cube.
+        assert!(dims_in(cs!(x = 0)).is_empty());
+    }
+}
@@ -0,0 +1,273 @@
+use crate::syntax::{ConcreteSyntax, ConcreteSyntax::*, SyntaxRef};
+use crate::termbuilder::Term;
+
+/// A subterm of one of the terms being compared, along with its path from the root.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Site {
+    pub path: Vec<usize>,
+    pub sref: SyntaxRef,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Change {
+    /// A subterm of the new term with no counterpart in the old one.
+    Inserted(Site),
+    /// A subterm of the old term with no counterpart in the new one.
+    Deleted(Site),
+    /// A subterm of the old term that was replaced wholesale by one of the new term.
+    Changed(Site, Site),
+}
+
+/// Compare two terms, returning the smallest changes we could find that turn the old one into the new one.
+///
+/// Arguments of applications, disjuncts and conjuncts of cofibrations, and branches of splits
+/// are compared as lists, so adding an argument shows up as a single insertion rather than as
+/// every later argument changing. A branch of a split is two children, so adding one inserts
+/// its cofibration and its body.
+pub fn diff(old: &Term, new: &Term) -> Vec<Change> {
+    diff_subterms(old, old.root(), new, new.root())
+}
+
+pub fn diff_subterms(old: &Term, osref: SyntaxRef, new: &Term, nsref: SyntaxRef) -> Vec<Change> {
+    let mut differ = Differ {
+        old,
+        new,
+        changes: Vec::new(),
+    };
+    let osite = Site {
+        path: Vec::new(),
+        sref: osref,
+    };
+    let nsite = Site {
+        path: Vec::new(),
+        sref: nsref,
+    };
+    differ.diff(osite, nsite);
+    differ.changes
+}
+
+// The parts of a node that have to agree for us to compare it child by child.
+fn shape(cs: &ConcreteSyntax<SyntaxRef>) -> ConcreteSyntax<()> {
+    cs.map(|_| ())
+}
+
+// How a list-like node lays out its children: the number of children before the list starts,
+// and how many children make up each element of the list.
+fn list_layout(cs: &ConcreteSyntax<SyntaxRef>) -> Option<(usize, usize)> {
+    match cs {
+        Ap(_, _) => Some((1, 1)),
+        Join(_) | Meet(_) => Some((0, 1)),
+        CofSplit(_) => Some((0, 2)),
+        _ => None,
+    }
+}
+
+fn same_list_kind(ocs: &ConcreteSyntax<SyntaxRef>, ncs: &ConcreteSyntax<SyntaxRef>) -> bool {
+    matches!(
+        (ocs, ncs),
+        (Ap(_, _), Ap(_, _)) | (Join(_), Join(_)) | (Meet(_), Meet(_)) | (CofSplit(_), CofSplit(_))
+    )
+}
+
+struct Differ<'a> {
+    old: &'a Term,
+    new: &'a Term,
+    changes: Vec<Change>,
+}
+
+impl<'a> Differ<'a> {
+    fn equal(&self, osref: SyntaxRef, nsref: SyntaxRef) -> bool {
+        let (ocs, ncs) = (&self.old.map[osref], &self.new.map[nsref]);
+        shape(ocs) == shape(ncs)
+            && ocs
+                .children()
+                .into_iter()
+                .zip(ncs.children())
+                .all(|(o, n)| self.equal(*o, *n))
+    }
+
+    fn child(site: &Site, i: usize, sref: SyntaxRef) -> Site {
+        let mut path = site.path.clone();
+        path.push(i);
+        Site { path, sref }
+    }
+
+    fn diff(&mut self, osite: Site, nsite: Site) {
+        let (ocs, ncs) = (&self.old.map[osite.sref], &self.new.map[nsite.sref]);
+        let (ochildren, nchildren) = (ocs.children(), ncs.children());
+        if shape(ocs) == shape(ncs) {
+            for (i, (o, n)) in ochildren.into_iter().zip(nchildren).enumerate() {
+                self.diff(Differ::child(&osite, i, *o), Differ::child(&nsite, i, *n));
+            }
+        } else if same_list_kind(ocs, ncs) {
+            let (prefix, width) = list_layout(ocs).unwrap();
+            for i in 0..prefix {
+                let (o, n) = (*ochildren[i], *nchildren[i]);
+                self.diff(Differ::child(&osite, i, o), Differ::child(&nsite, i, n));
+            }
+            let ochildren: Vec<SyntaxRef> = ochildren.into_iter().copied().collect();
+            let nchildren: Vec<SyntaxRef> = nchildren.into_iter().copied().collect();
+            self.diff_list(&osite, &nsite, &ochildren, &nchildren, prefix, width);
+        } else {
+            self.changes.push(Change::Changed(osite, nsite));
+        }
+    }
+
+    // Line up the elements of two lists by finding their longest common subsequence of equal
+    // elements. Whatever is left over between two matched elements is paired up and compared
+    // where possible, and inserted or deleted otherwise.
+    fn diff_list(
+        &mut self,
+        osite: &Site,
+        nsite: &Site,
+        ochildren: &[SyntaxRef],
+        nchildren: &[SyntaxRef],
+        prefix: usize,
+        width: usize,
+    ) {
+        let olen = (ochildren.len() - prefix) / width;
+        let nlen = (nchildren.len() - prefix) / width;
+        let elem_equal = |differ: &Differ, o: usize, n: usize| {
+            (0..width).all(|k| {
+                differ.equal(
+                    ochildren[prefix + o * width + k],
+                    nchildren[prefix + n * width + k],
+                )
+            })
+        };
+
+        let mut lcs = vec![vec![0; nlen + 1]; olen + 1];
+        for o in (0..olen).rev() {
+            for n in (0..nlen).rev() {
+                lcs[o][n] = if elem_equal(self, o, n) {
+                    lcs[o + 1][n + 1] + 1
+                } else {
+                    lcs[o + 1][n].max(lcs[o][n + 1])
+                };
+            }
+        }
+
+        let mut matches = Vec::new();
+        let (mut o, mut n) = (0, 0);
+        while o < olen && n < nlen {
+            if elem_equal(self, o, n) {
+                matches.push((o, n));
+                o += 1;
+                n += 1;
+            } else if lcs[o + 1][n] >= lcs[o][n + 1] {
+                o += 1;
+            } else {
+                n += 1;
+            }
+        }
+        matches.push((olen, nlen));
+
+        let (mut o, mut n) = (0, 0);
+        for (mo, mn) in matches {
+            while o < mo || n < mn {
+                for k in 0..width {
+                    let oi = prefix + o * width + k;
+                    let ni = prefix + n * width + k;
+                    if o < mo && n < mn {
+                        let (osub, nsub) = (ochildren[oi], nchildren[ni]);
+                        self.diff(
+                            Differ::child(osite, oi, osub),
+                            Differ::child(nsite, ni, nsub),
+                        );
+                    } else if o < mo {
+                        let deleted = Differ::child(osite, oi, ochildren[oi]);
+                        self.changes.push(Change::Deleted(deleted));
+                    } else {
+                        let inserted = Differ::child(nsite, ni, nchildren[ni]);
+                        self.changes.push(Change::Inserted(inserted));
+                    }
+                }
+                if o < mo {
+                    o += 1;
+                }
+                if n < mn {
+                    n += 1;
+                }
+            }
+            o += 1;
+            n += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::syntax::{cs, SyntaxRec};
+
+    fn term(cs: ConcreteSyntax<SyntaxRec>) -> Term {
+        Term::from_syntax(cs).0
+    }
+
+    // The paths of the changes, along with how each of them was changed.
+    fn paths(old: ConcreteSyntax<SyntaxRec>, new: ConcreteSyntax<SyntaxRec>) -> Vec<String> {
+        diff(&term(old), &term(new))
+            .into_iter()
+            .map(|change| match change {
+                Change::Inserted(site) => format!("+{:?}", site.path),
+                Change::Deleted(site) => format!("-{:?}", site.path),
+                Change::Changed(osite, nsite) => format!("{:?}~{:?}", osite.path, nsite.path),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn equal_terms() {
+        assert!(paths(cs!(lam x => f x ?), cs!(lam x => f x ?)).is_empty());
+    }
+
+    #[test]
+    fn changed_subterms() {
+        assert_eq!(
+            paths(cs!(lam x => f x), cs!(lam x => g x)),
+            ["[0, 0]~[0, 0]"]
+        );
+        assert_eq!(paths(cs!(f x), cs!([a, b])), ["[]~[]"]);
+        assert_eq!(
+            paths(cs!(hcom 0 1 ?), cs!(hcom 1 0 ?)),
+            ["[0]~[0]", "[1]~[1]"]
+        );
+    }
+
+    #[test]
+    fn list_elements() {
+        assert_eq!(paths(cs!(f a c), cs!(f a b c)), ["+[2]"]);
+        assert_eq!(paths(cs!(f a b c), cs!(f a c)), ["-[2]"]);
+        assert_eq!(paths(cs!(f a b c), cs!(f a d c)), ["[2]~[2]"]);
+        assert_eq!(paths(cs!(g a b), cs!(f a b c)), ["[0]~[0]", "+[3]"]);
+        assert_eq!(
+            paths(cs!(join((i = 0), (i = 1))), cs!(join((i = 1)))),
+            ["-[0]"]
+        );
+    }
+
+    #[test]
+    fn split_branches() {
+        assert_eq!(
+            paths(
+                cs!(split { i = 0 => a, i = 1 => b }),
+                cs!(split { i = 0 => a, j = 0 => c, i = 1 => b })
+            ),
+            ["+[2]", "+[3]"]
+        );
+        assert_eq!(
+            paths(
+                cs!(split { i = 0 => a, j = 0 => c, i = 1 => b }),
+                cs!(split { i = 0 => a, i = 1 => b })
+            ),
+            ["-[2]", "-[3]"]
+        );
+        assert_eq!(
+            paths(
+                cs!(split { i = 0 => a, i = 1 => b }),
+                cs!(split { i = 0 => a, i = 1 => c })
+            ),
+            ["[3]~[3]"]
+        );
+    }
+}
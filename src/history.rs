@@ -0,0 +1,244 @@
+use std::collections::HashSet;
+
+use crate::syntax::{ConcreteSyntax, Hole, SyntaxRec, SyntaxRef};
+use crate::termbuilder::{HoleTarget, Term, TermError};
+
+// [NOTE: Edit paths] Edits remember where they happened as a path from the root rather than
+// as a 'SyntaxRef'. Redoing an edit allocates fresh nodes for everything below it, so any
+// references into that subterm would be dangling by the time a later edit gets redone.
+//
+// They also remember which names were reserved, so that undoing an edit gives back the fresh
+// names it took. Otherwise redoing the same tactics after an undo would pick different names
+// than replaying them from scratch does.
+#[derive(Clone, Debug)]
+struct Edit {
+    path: Vec<usize>,
+    before: SyntaxRec,
+    after: SyntaxRec,
+    reserved_before: HashSet<String>,
+    reserved_after: HashSet<String>,
+}
+
+/// A term along with the edits that produced it, so that they can be undone and redone.
+pub struct History {
+    term: Term,
+    undo: Vec<Edit>,
+    redo: Vec<Edit>,
+}
+
+impl History {
+    pub fn new(term: Term) -> History {
+        History {
+            term,
+            undo: Vec::new(),
+            redo: Vec::new(),
+        }
+    }
+
+    pub fn term(&self) -> &Term {
+        &self.term
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    fn snapshot(&self, sref: SyntaxRef) -> SyntaxRec {
+        SyntaxRec::new(self.term.rerecurse(sref))
+    }
+
+    fn record<F>(&mut self, sref: SyntaxRef, edit: F) -> Result<SyntaxRef, TermError>
+    where
+        F: FnOnce(&mut Term) -> Result<SyntaxRef, TermError>,
+    {
+        let path = self
+            .term
+            .path_of(sref)
+            .ok_or(TermError::DanglingRef(sref))?;
+        let before = self.snapshot(sref);
+        let reserved_before = self.term.reserved().clone();
+        let sref = edit(&mut self.term)?;
+        let after = self.snapshot(sref);
+        self.undo.push(Edit {
+            path,
+            before,
+            after,
+            reserved_before,
+            reserved_after: self.term.reserved().clone(),
+        });
+        self.redo.clear();
+        Ok(sref)
+    }
+
+    pub fn plug_at(
+        &mut self,
+        target: &HoleTarget,
+        filler: ConcreteSyntax<SyntaxRec>,
+    ) -> Result<SyntaxRef, TermError> {
+        let hole = self.term.find_hole(target)?;
+        self.record(hole, |term| term.plug_at(&HoleTarget::Ref(hole), filler))
+    }
+
+    /// Plug a hole with a filler that needs to know about the term, eg. to take fresh names from it.
+    pub fn plug_with<F>(&mut self, target: &HoleTarget, build: F) -> Result<SyntaxRef, TermError>
+    where
+        F: FnOnce(&mut Term) -> ConcreteSyntax<SyntaxRec>,
+    {
+        let hole = self.term.find_hole(target)?;
+        self.record(hole, |term| {
+            let filler = build(term);
+            term.plug_at(&HoleTarget::Ref(hole), filler)
+        })
+    }
+
+    pub fn plug(&mut self, filler: ConcreteSyntax<SyntaxRec>) -> Result<SyntaxRef, TermError> {
+        self.plug_at(&HoleTarget::Next, filler)
+    }
+
+    pub fn paste(
+        &mut self,
+        target: &HoleTarget,
+        source: &Term,
+        sref: SyntaxRef,
+    ) -> Result<SyntaxRef, TermError> {
+        let hole = self.term.find_hole(target)?;
+        self.record(hole, |term| {
+            term.paste(&HoleTarget::Ref(hole), source, sref)
+        })
+    }
+
+    pub fn replace(
+        &mut self,
+        sref: SyntaxRef,
+        filler: ConcreteSyntax<SyntaxRec>,
+    ) -> Result<SyntaxRef, TermError> {
+        self.record(sref, |term| term.replace(sref, filler))
+    }
+
+    pub fn unplug(&mut self, sref: SyntaxRef) -> Result<SyntaxRef, TermError> {
+        let hole = ConcreteSyntax::Hole(Hole {
+            name: None,
+            silent: false,
+        });
+        self.replace(sref, hole)
+    }
+
+    // Put 'contents' at 'path', which must exist since the edits are replayed in order.
+    fn restore(&mut self, path: &[usize], contents: &SyntaxRec) {
+        let sref = self
+            .term
+            .lookup(path)
+            .expect("Edit history is out of sync with the term!");
+        self.term
+            .replace(sref, (*contents.value).clone())
+            .expect("Edit history is out of sync with the term!");
+    }
+
+    /// Undo the last edit, returning false if there was nothing to undo.
+    pub fn undo(&mut self) -> bool {
+        match self.undo.pop() {
+            Some(edit) => {
+                self.restore(&edit.path, &edit.before);
+                self.term.set_reserved(edit.reserved_before.clone());
+                self.redo.push(edit);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Redo the last undone edit, returning false if there was nothing to redo.
+    pub fn redo(&mut self) -> bool {
+        match self.redo.pop() {
+            Some(edit) => {
+                self.restore(&edit.path, &edit.after);
+                self.term.set_reserved(edit.reserved_after.clone());
+                self.undo.push(edit);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::syntax::cs;
+
+    fn history(cs: ConcreteSyntax<SyntaxRec>) -> (History, SyntaxRef) {
+        let (term, sref) = Term::from_syntax(cs);
+        (History::new(term), sref)
+    }
+
+    fn printed(history: &History) -> String {
+        history.term().to_string()
+    }
+
+    #[test]
+    fn undo_and_redo_plugs() {
+        let (mut history, _) = history(cs!(f??));
+        assert!(!history.can_undo());
+        history.plug(cs!(a)).expect("Failed to plug term");
+        history.plug(cs!(b)).expect("Failed to plug term");
+        assert_eq!(printed(&history), "f a b");
+
+        assert!(history.undo());
+        assert!(history.undo());
+        assert!(!history.undo());
+        assert_eq!(printed(&history), "f ? ?");
+        assert!(history.can_redo());
+
+        assert!(history.redo());
+        assert_eq!(printed(&history), "f a ?");
+        // A new edit forgets whatever was left to redo.
+        history.plug(cs!(c)).expect("Failed to plug term");
+        assert_eq!(printed(&history), "f a c");
+        assert!(!history.can_redo());
+        assert!(!history.redo());
+    }
+
+    #[test]
+    fn undo_and_redo_replacements() {
+        let (mut history, app) = history(cs!(f (g a) c));
+        history
+            .replace(app, cs!(h?))
+            .expect("Failed to replace term");
+        assert_eq!(printed(&history), "h ?");
+        assert!(history.undo());
+        assert_eq!(printed(&history), "f (g a) c");
+        assert!(history.redo());
+        assert_eq!(printed(&history), "h ?");
+
+        let arg = history.term().lookup(&[1]).expect("Missing argument");
+        history.unplug(arg).expect("Failed to unplug term");
+        assert_eq!(printed(&history), "h ?");
+        assert!(history.undo());
+        assert!(history.undo());
+        assert_eq!(printed(&history), "f (g a) c");
+
+        let arg = history.term().lookup(&[1]).expect("Missing argument");
+        history.unplug(arg).expect("Failed to unplug term");
+        assert_eq!(printed(&history), "f ? c");
+        assert!(history.undo());
+        assert_eq!(printed(&history), "f (g a) c");
+    }
+
+    #[test]
+    fn undo_and_redo_pastes() {
+        let (source, sref) = Term::from_syntax(cs!(lam i => g i));
+        let (mut history, _) = history(cs!(f?));
+        history
+            .paste(&HoleTarget::Next, &source, sref)
+            .expect("Failed to paste term");
+        assert_eq!(printed(&history), "f (λ i => g i)");
+        assert!(history.undo());
+        assert_eq!(printed(&history), "f ?");
+        assert!(history.redo());
+        assert_eq!(printed(&history), "f (λ i => g i)");
+    }
+}
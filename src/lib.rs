@@ -0,0 +1,23 @@
+pub mod camera;
+pub mod cof;
+pub mod coverage;
+pub mod cube;
+pub mod diff;
+pub mod history;
+pub mod label;
+pub mod linalg;
+pub mod messages;
+pub mod parser;
+pub mod partialcube;
+pub mod pretty;
+pub mod render;
+pub mod scope;
+pub mod script;
+pub mod server;
+pub mod skeleton;
+pub mod subst;
+pub mod syntax;
+pub mod system;
+pub mod tactic;
+pub mod termbuilder;
+pub mod vertex;
@@ -1,6 +1,8 @@
 use std::collections::HashMap;
 
-use serde::Deserialize;
+use serde::{ser::SerializeSeq, Deserialize, Serialize, Serializer};
+
+use crate::syntax::SyntaxRec;
 
 #[derive(Debug, Deserialize)]
 pub struct Label {
@@ -17,6 +19,11 @@ pub struct CubeMessage {
 
 #[derive(Debug, Deserialize)]
 pub struct DisplayGoal {
+    // The hole that this goal belongs to, so that we can tell when cooltt is re-sending
+    // the goal we are already working on. cooltt should always send it: goals without one
+    // can only be told apart by their dimensions and context.
+    #[serde(default)]
+    pub hole: Option<String>,
     pub dims: Vec<String>,
     pub labels: Vec<Label>,
     pub context: String,
@@ -27,3 +34,93 @@ pub struct DisplayGoal {
 pub enum Message {
     DisplayGoal(DisplayGoal),
 }
+
+/// Messages that we send back to cooltt.
+#[derive(Debug)]
+pub enum Outbound {
+    /// Fill the hole with a term we have built.
+    FillHole {
+        hole: Option<String>,
+        term: SyntaxRec,
+    },
+    /// Ask for the goal of a hole to be sent again, eg. after it has been edited in cooltt.
+    RequestGoal {
+        hole: Option<String>,
+    },
+    /// We got a goal and are displaying it.
+    Ack,
+    Error(String),
+}
+
+// Messages are encoded like syntax, see [NOTE: Wire format].
+impl Serialize for Outbound {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        #[derive(Serialize)]
+        struct FillHole<'a> {
+            hole: &'a Option<String>,
+            term: &'a SyntaxRec,
+        }
+
+        #[derive(Serialize)]
+        struct RequestGoal<'a> {
+            hole: &'a Option<String>,
+        }
+
+        let mut seq = serializer.serialize_seq(None)?;
+        match self {
+            Outbound::FillHole { hole, term } => {
+                seq.serialize_element("FillHole")?;
+                seq.serialize_element(&FillHole { hole, term })?;
+            }
+            Outbound::RequestGoal { hole } => {
+                seq.serialize_element("RequestGoal")?;
+                seq.serialize_element(&RequestGoal { hole })?;
+            }
+            Outbound::Ack => seq.serialize_element("Ack")?,
+            Outbound::Error(msg) => {
+                seq.serialize_element("Error")?;
+                seq.serialize_element(msg)?;
+            }
+        }
+        seq.end()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::syntax::cs;
+    use serde_json::json;
+
+    fn wire(msg: Outbound) -> serde_json::Value {
+        serde_json::to_value(msg).expect("Failed to serialize message")
+    }
+
+    #[test]
+    fn wire_format() {
+        assert_eq!(
+            wire(Outbound::FillHole {
+                hole: Some("goal".to_string()),
+                term: SyntaxRec::new(cs!(f?)),
+            }),
+            json!(["FillHole", {
+                "hole": "goal",
+                "term": {"node": ["Ap",
+                    {"node": ["Var", ["User", ["f"]]]},
+                    [{"node": ["Hole", {"name": null, "silent": false}]}]]}
+            }])
+        );
+        assert_eq!(
+            wire(Outbound::RequestGoal { hole: None }),
+            json!(["RequestGoal", {"hole": null}])
+        );
+        assert_eq!(wire(Outbound::Ack), json!(["Ack"]));
+        assert_eq!(
+            wire(Outbound::Error("No goal".to_string())),
+            json!(["Error", "No goal"])
+        );
+    }
+}
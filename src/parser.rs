@@ -0,0 +1,789 @@
+use std::fmt;
+
+use crate::syntax::{Cell, ConcreteSyntax, ConcreteSyntax::*, Hole, Ident, SyntaxRec};
+
+/// A byte range into the parsed source.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+#[derive(Clone, Debug)]
+pub struct ParseError {
+    pub span: Span,
+    pub message: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}..{}: {}",
+            self.span.start, self.span.end, self.message
+        )
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Ident(Vec<String>),
+    Num(u32),
+    Hole(Option<String>, bool),
+    Lambda,
+    FatArrow,
+    Arrow,
+    Times,
+    Colon,
+    ColonEq,
+    Comma,
+    Dot,
+    Eq,
+    Le,
+    Join,
+    Meet,
+    LParen,
+    RParen,
+    LSquare,
+    RSquare,
+    LBrace,
+    RBrace,
+    Bar,
+    Underscore,
+    Top,
+    Bot,
+    Type,
+    Dim,
+    Cof,
+    HCom,
+    HFill,
+    Com,
+    Coe,
+    Let,
+    In,
+    Fst,
+    Snd,
+    Ext,
+    With,
+    V,
+    VProj,
+    Eof,
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Token::Ident(parts) => write!(f, "identifier '{}'", parts.join(".")),
+            Token::Num(n) => write!(f, "number '{}'", n),
+            Token::Hole(_, _) => write!(f, "hole"),
+            Token::Lambda => write!(f, "'λ'"),
+            Token::FatArrow => write!(f, "'=>'"),
+            Token::Arrow => write!(f, "'→'"),
+            Token::Times => write!(f, "'×'"),
+            Token::Colon => write!(f, "':'"),
+            Token::ColonEq => write!(f, "':='"),
+            Token::Comma => write!(f, "','"),
+            Token::Dot => write!(f, "'.'"),
+            Token::Eq => write!(f, "'='"),
+            Token::Le => write!(f, "'<='"),
+            Token::Join => write!(f, "'\\/'"),
+            Token::Meet => write!(f, "'/\\'"),
+            Token::LParen => write!(f, "'('"),
+            Token::RParen => write!(f, "')'"),
+            Token::LSquare => write!(f, "'['"),
+            Token::RSquare => write!(f, "']'"),
+            Token::LBrace => write!(f, "'{{'"),
+            Token::RBrace => write!(f, "'}}'"),
+            Token::Bar => write!(f, "'|'"),
+            Token::Underscore => write!(f, "'_'"),
+            Token::Top => write!(f, "'#t'"),
+            Token::Bot => write!(f, "'#f'"),
+            Token::Type => write!(f, "'type'"),
+            Token::Dim => write!(f, "'dim'"),
+            Token::Cof => write!(f, "'cof'"),
+            Token::HCom => write!(f, "'hcom'"),
+            Token::HFill => write!(f, "'hfill'"),
+            Token::Com => write!(f, "'com'"),
+            Token::Coe => write!(f, "'coe'"),
+            Token::Let => write!(f, "'let'"),
+            Token::In => write!(f, "'in'"),
+            Token::Fst => write!(f, "'fst'"),
+            Token::Snd => write!(f, "'snd'"),
+            Token::Ext => write!(f, "'ext'"),
+            Token::With => write!(f, "'with'"),
+            Token::V => write!(f, "'V'"),
+            Token::VProj => write!(f, "'vproj'"),
+            Token::Eof => write!(f, "end of input"),
+        }
+    }
+}
+
+fn is_ident_start(c: char) -> bool {
+    (c.is_alphabetic() && c != 'λ') || c == '_'
+}
+
+fn is_ident_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '\''
+}
+
+fn lex(input: &str) -> Result<Vec<(Token, Span)>, ParseError> {
+    let mut tokens = Vec::new();
+    let mut chars = input.char_indices().peekable();
+
+    // Reads a (possibly qualified) name starting at 'start', returning it and its end offset.
+    let read_name = |chars: &mut std::iter::Peekable<std::str::CharIndices>, start: usize| {
+        let mut end = start;
+        while let Some(&(i, c)) = chars.peek() {
+            let qualified = c == '.' && input[i + 1..].chars().next().is_some_and(is_ident_start);
+            if is_ident_char(c) || (qualified && i > start) {
+                end = i + c.len_utf8();
+                chars.next();
+            } else {
+                break;
+            }
+        }
+        (&input[start..end], end)
+    };
+
+    while let Some(&(start, c)) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+            continue;
+        }
+
+        let single = |tok: Token| (tok, start + c.len_utf8());
+        let (tok, end) = match c {
+            '(' => single(Token::LParen),
+            ')' => single(Token::RParen),
+            '[' => single(Token::LSquare),
+            ']' => single(Token::RSquare),
+            '{' => single(Token::LBrace),
+            '}' => single(Token::RBrace),
+            '|' => single(Token::Bar),
+            ',' => single(Token::Comma),
+            '.' => single(Token::Dot),
+            '*' | '×' => single(Token::Times),
+            '→' => single(Token::Arrow),
+            'λ' => single(Token::Lambda),
+            '∨' => single(Token::Join),
+            '∧' => single(Token::Meet),
+            '=' | '<' | '\\' | '/' | '#' | '-' | ':' => {
+                chars.next();
+                let next = chars.peek().map(|&(_, c)| c);
+                let tok = match (c, next) {
+                    ('=', Some('>')) => Some(Token::FatArrow),
+                    ('<', Some('=')) => Some(Token::Le),
+                    ('\\', Some('/')) => Some(Token::Join),
+                    ('/', Some('\\')) => Some(Token::Meet),
+                    ('#', Some('t')) => Some(Token::Top),
+                    ('#', Some('f')) => Some(Token::Bot),
+                    ('-', Some('>')) => Some(Token::Arrow),
+                    (':', Some('=')) => Some(Token::ColonEq),
+                    _ => None,
+                };
+                let (tok, end) = match (tok, c) {
+                    (Some(tok), _) => {
+                        chars.next();
+                        (tok, start + 2)
+                    }
+                    (None, '=') => (Token::Eq, start + 1),
+                    (None, '\\') => (Token::Lambda, start + 1),
+                    (None, ':') => (Token::Colon, start + 1),
+                    (None, _) => {
+                        return Err(ParseError {
+                            span: Span {
+                                start,
+                                end: start + 1,
+                            },
+                            message: format!("unexpected character '{}'", c),
+                        })
+                    }
+                };
+                tokens.push((tok, Span { start, end }));
+                continue;
+            }
+            '?' | '!' => {
+                chars.next();
+                let (name, end) = match chars.peek() {
+                    Some(&(i, c)) if is_ident_start(c) => {
+                        let (name, end) = read_name(&mut chars, i);
+                        (Some(name.to_string()), end)
+                    }
+                    _ => (None, start + 1),
+                };
+                tokens.push((Token::Hole(name, c == '!'), Span { start, end }));
+                continue;
+            }
+            c if c.is_ascii_digit() => {
+                let mut end = start;
+                while let Some(&(i, c)) = chars.peek() {
+                    if c.is_ascii_digit() {
+                        end = i + 1;
+                        chars.next();
+                    } else {
+                        break;
+                    }
+                }
+                let span = Span { start, end };
+                let n = input[start..end].parse().map_err(|_| ParseError {
+                    span,
+                    message: "numeric literal is too large".to_string(),
+                })?;
+                tokens.push((Token::Num(n), span));
+                continue;
+            }
+            c if is_ident_start(c) => {
+                let (name, end) = read_name(&mut chars, start);
+                let tok = match name {
+                    "_" => Token::Underscore,
+                    "type" => Token::Type,
+                    "dim" => Token::Dim,
+                    "cof" => Token::Cof,
+                    "hcom" => Token::HCom,
+                    "hfill" => Token::HFill,
+                    "com" => Token::Com,
+                    "coe" => Token::Coe,
+                    "let" => Token::Let,
+                    "in" => Token::In,
+                    "fst" => Token::Fst,
+                    "snd" => Token::Snd,
+                    "ext" => Token::Ext,
+                    "with" => Token::With,
+                    "V" => Token::V,
+                    "vproj" => Token::VProj,
+                    _ => Token::Ident(name.split('.').map(|s| s.to_string()).collect()),
+                };
+                tokens.push((tok, Span { start, end }));
+                continue;
+            }
+            _ => {
+                return Err(ParseError {
+                    span: Span {
+                        start,
+                        end: start + c.len_utf8(),
+                    },
+                    message: format!("unexpected character '{}'", c),
+                })
+            }
+        };
+        chars.next();
+        tokens.push((tok, Span { start, end }));
+    }
+
+    tokens.push((
+        Token::Eof,
+        Span {
+            start: input.len(),
+            end: input.len(),
+        },
+    ));
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<(Token, Span)>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> &Token {
+        &self.tokens[self.pos].0
+    }
+
+    fn span(&self) -> Span {
+        self.tokens[self.pos].1
+    }
+
+    fn advance(&mut self) -> Token {
+        let tok = self.tokens[self.pos].0.clone();
+        if tok != Token::Eof {
+            self.pos += 1;
+        }
+        tok
+    }
+
+    fn error<T>(&self, expected: &str) -> Result<T, ParseError> {
+        Err(ParseError {
+            span: self.span(),
+            message: format!("expected {}, found {}", expected, self.peek()),
+        })
+    }
+
+    fn expect(&mut self, tok: Token) -> Result<(), ParseError> {
+        if *self.peek() == tok {
+            self.advance();
+            Ok(())
+        } else {
+            self.error(&tok.to_string())
+        }
+    }
+
+    fn binders(&mut self) -> Result<Vec<Ident>, ParseError> {
+        let mut vars = Vec::new();
+        loop {
+            match self.peek() {
+                Token::Ident(parts) => vars.push(Ident::User(parts.clone())),
+                Token::Underscore => vars.push(Ident::Anon),
+                _ => break,
+            }
+            self.advance();
+        }
+        if vars.is_empty() {
+            return self.error("a binder");
+        }
+        Ok(vars)
+    }
+
+    fn branches(&mut self) -> Result<Vec<(SyntaxRec, SyntaxRec)>, ParseError> {
+        let mut branches = Vec::new();
+        if *self.peek() != Token::RSquare {
+            loop {
+                let cof = self.join()?;
+                self.expect(Token::FatArrow)?;
+                let body = self.term()?;
+                branches.push((SyntaxRec::new(cof), SyntaxRec::new(body)));
+                if *self.peek() != Token::Bar {
+                    break;
+                }
+                self.advance();
+            }
+        }
+        Ok(branches)
+    }
+
+    // Does a telescope cell '(x y : A)' start here, as opposed to a parenthesized term?
+    fn at_cell(&self) -> bool {
+        if *self.peek() != Token::LParen {
+            return false;
+        }
+        let mut i = self.pos + 1;
+        while let Token::Ident(_) | Token::Underscore = self.tokens[i].0 {
+            i += 1;
+        }
+        i > self.pos + 1 && self.tokens[i].0 == Token::Colon
+    }
+
+    fn term(&mut self) -> Result<ConcreteSyntax<SyntaxRec>, ParseError> {
+        match self.peek() {
+            Token::Lambda => {
+                self.advance();
+                let vars = self.binders()?;
+                self.expect(Token::FatArrow)?;
+                let body = self.term()?;
+                Ok(Lam(vars, SyntaxRec::new(body)))
+            }
+            Token::Let => {
+                self.advance();
+                let var = match self.peek() {
+                    Token::Ident(parts) => Ident::User(parts.clone()),
+                    Token::Underscore => Ident::Anon,
+                    _ => return self.error("a binder"),
+                };
+                self.advance();
+                self.expect(Token::ColonEq)?;
+                let def = self.term()?;
+                self.expect(Token::In)?;
+                let body = self.term()?;
+                Ok(Let(SyntaxRec::new(def), var, SyntaxRec::new(body)))
+            }
+            Token::Ext => {
+                self.advance();
+                let vars = self.binders()?;
+                self.expect(Token::FatArrow)?;
+                let tp = self.term()?;
+                self.expect(Token::With)?;
+                self.expect(Token::LSquare)?;
+                let branches = self.branches()?;
+                self.expect(Token::RSquare)?;
+                Ok(Ext(vars, SyntaxRec::new(tp), branches))
+            }
+            _ if self.at_cell() => {
+                let mut cells = Vec::new();
+                while self.at_cell() {
+                    self.advance();
+                    let names = self.binders()?;
+                    self.expect(Token::Colon)?;
+                    let tp = SyntaxRec::new(self.term()?);
+                    self.expect(Token::RParen)?;
+                    cells.push(Cell { names, tp });
+                }
+                match self.peek() {
+                    Token::Arrow => {
+                        self.advance();
+                        Ok(Pi(cells, SyntaxRec::new(self.term()?)))
+                    }
+                    Token::Times => {
+                        self.advance();
+                        Ok(Sg(cells, SyntaxRec::new(self.term()?)))
+                    }
+                    _ => self.error("'→' or '×'"),
+                }
+            }
+            _ => self.arrow(),
+        }
+    }
+
+    fn anon_cell(tp: ConcreteSyntax<SyntaxRec>) -> Vec<Cell<SyntaxRec>> {
+        vec![Cell {
+            names: vec![Ident::Anon],
+            tp: SyntaxRec::new(tp),
+        }]
+    }
+
+    fn arrow(&mut self) -> Result<ConcreteSyntax<SyntaxRec>, ParseError> {
+        let dom = self.prod()?;
+        if *self.peek() == Token::Arrow {
+            self.advance();
+            let cod = self.term()?;
+            Ok(Pi(Parser::anon_cell(dom), SyntaxRec::new(cod)))
+        } else {
+            Ok(dom)
+        }
+    }
+
+    fn prod(&mut self) -> Result<ConcreteSyntax<SyntaxRec>, ParseError> {
+        let fst = self.join()?;
+        if *self.peek() == Token::Times {
+            self.advance();
+            let snd = self.prod()?;
+            Ok(Sg(Parser::anon_cell(fst), SyntaxRec::new(snd)))
+        } else {
+            Ok(fst)
+        }
+    }
+
+    fn join(&mut self) -> Result<ConcreteSyntax<SyntaxRec>, ParseError> {
+        let mut disjuncts = vec![self.meet()?];
+        while *self.peek() == Token::Join {
+            self.advance();
+            disjuncts.push(self.meet()?);
+        }
+        if disjuncts.len() == 1 {
+            Ok(disjuncts.pop().unwrap())
+        } else {
+            Ok(Join(disjuncts.into_iter().map(SyntaxRec::new).collect()))
+        }
+    }
+
+    fn meet(&mut self) -> Result<ConcreteSyntax<SyntaxRec>, ParseError> {
+        let mut conjuncts = vec![self.cof()?];
+        while *self.peek() == Token::Meet {
+            self.advance();
+            conjuncts.push(self.cof()?);
+        }
+        if conjuncts.len() == 1 {
+            Ok(conjuncts.pop().unwrap())
+        } else {
+            Ok(Meet(conjuncts.into_iter().map(SyntaxRec::new).collect()))
+        }
+    }
+
+    fn cof(&mut self) -> Result<ConcreteSyntax<SyntaxRec>, ParseError> {
+        let lhs = self.app()?;
+        match self.peek() {
+            Token::Eq => {
+                self.advance();
+                let rhs = self.app()?;
+                Ok(CofEq(SyntaxRec::new(lhs), SyntaxRec::new(rhs)))
+            }
+            Token::Le => {
+                self.advance();
+                let rhs = self.app()?;
+                Ok(CofLe(SyntaxRec::new(lhs), SyntaxRec::new(rhs)))
+            }
+            _ => Ok(lhs),
+        }
+    }
+
+    fn app(&mut self) -> Result<ConcreteSyntax<SyntaxRec>, ParseError> {
+        match self.peek() {
+            Token::HCom => {
+                self.advance();
+                let src = self.atom()?;
+                let trg = self.atom()?;
+                let tube = self.braced()?;
+                Ok(HComChk(
+                    SyntaxRec::new(src),
+                    SyntaxRec::new(trg),
+                    SyntaxRec::new(tube),
+                ))
+            }
+            Token::HFill => {
+                self.advance();
+                let src = self.atom()?;
+                let tube = self.braced()?;
+                Ok(HFillChk(SyntaxRec::new(src), SyntaxRec::new(tube)))
+            }
+            Token::Com => {
+                self.advance();
+                let fam = self.atom()?;
+                let src = self.atom()?;
+                let trg = self.atom()?;
+                let cof = self.atom()?;
+                let tube = self.braced()?;
+                Ok(Com(
+                    SyntaxRec::new(fam),
+                    SyntaxRec::new(src),
+                    SyntaxRec::new(trg),
+                    SyntaxRec::new(cof),
+                    SyntaxRec::new(tube),
+                ))
+            }
+            Token::Coe => {
+                self.advance();
+                let fam = self.atom()?;
+                let src = self.atom()?;
+                let trg = self.atom()?;
+                let body = self.atom()?;
+                Ok(Coe(
+                    SyntaxRec::new(fam),
+                    SyntaxRec::new(src),
+                    SyntaxRec::new(trg),
+                    SyntaxRec::new(body),
+                ))
+            }
+            Token::V => {
+                self.advance();
+                let r = self.atom()?;
+                let a = self.atom()?;
+                let b = self.atom()?;
+                let e = self.atom()?;
+                Ok(V(
+                    SyntaxRec::new(r),
+                    SyntaxRec::new(a),
+                    SyntaxRec::new(b),
+                    SyntaxRec::new(e),
+                ))
+            }
+            Token::Fst => {
+                self.advance();
+                Ok(Fst(SyntaxRec::new(self.atom()?)))
+            }
+            Token::Snd => {
+                self.advance();
+                Ok(Snd(SyntaxRec::new(self.atom()?)))
+            }
+            Token::VProj => {
+                self.advance();
+                Ok(VProj(SyntaxRec::new(self.atom()?)))
+            }
+            _ => {
+                let head = self.atom()?;
+                let mut args = Vec::new();
+                while self.at_atom() {
+                    args.push(SyntaxRec::new(self.atom()?));
+                }
+                if args.is_empty() {
+                    Ok(head)
+                } else {
+                    Ok(Ap(SyntaxRec::new(head), args))
+                }
+            }
+        }
+    }
+
+    // The tube of an hcom or hfill is usually written in braces, but any atom will do.
+    fn braced(&mut self) -> Result<ConcreteSyntax<SyntaxRec>, ParseError> {
+        if *self.peek() == Token::LBrace {
+            self.advance();
+            let tm = self.term()?;
+            self.expect(Token::RBrace)?;
+            Ok(tm)
+        } else {
+            self.atom()
+        }
+    }
+
+    fn at_atom(&self) -> bool {
+        matches!(
+            self.peek(),
+            Token::Ident(_)
+                | Token::Num(_)
+                | Token::Hole(_, _)
+                | Token::Underscore
+                | Token::Top
+                | Token::Bot
+                | Token::Type
+                | Token::Dim
+                | Token::Cof
+                | Token::LParen
+                | Token::LSquare
+        )
+    }
+
+    fn atom(&mut self) -> Result<ConcreteSyntax<SyntaxRec>, ParseError> {
+        if !self.at_atom() {
+            return self.error("a term");
+        }
+        let mut tm = match self.advance() {
+            Token::Ident(parts) => Var(Ident::User(parts)),
+            Token::Num(n) => Lit(n),
+            Token::Hole(name, silent) => Hole(Hole { name, silent }),
+            Token::Underscore => Underscore,
+            Token::Top => TopC,
+            Token::Bot => BotC,
+            Token::Type => Type,
+            Token::Dim => Dim,
+            Token::Cof => Cof,
+            Token::LParen => {
+                let tm = self.term()?;
+                self.expect(Token::RParen)?;
+                tm
+            }
+            // Both pairs and splits live in square brackets, and we can only tell
+            // them apart after the first component.
+            Token::LSquare => {
+                if *self.peek() == Token::RSquare {
+                    self.advance();
+                    return Ok(CofSplit(vec![]));
+                }
+                let fst = self.term()?;
+                let tm = match self.peek() {
+                    Token::Comma => {
+                        self.advance();
+                        let snd = self.term()?;
+                        Pair(SyntaxRec::new(fst), SyntaxRec::new(snd))
+                    }
+                    Token::FatArrow => {
+                        self.advance();
+                        let body = self.term()?;
+                        let mut branches = vec![(SyntaxRec::new(fst), SyntaxRec::new(body))];
+                        if *self.peek() == Token::Bar {
+                            self.advance();
+                            branches.append(&mut self.branches()?);
+                        }
+                        CofSplit(branches)
+                    }
+                    _ => return self.error("',' or '=>'"),
+                };
+                self.expect(Token::RSquare)?;
+                tm
+            }
+            _ => unreachable!(),
+        };
+        while *self.peek() == Token::Dot {
+            self.advance();
+            let field = match self.peek() {
+                Token::Ident(parts) => Ident::User(parts.clone()),
+                _ => return self.error("a field name"),
+            };
+            self.advance();
+            tm = Proj(SyntaxRec::new(tm), field);
+        }
+        Ok(tm)
+    }
+}
+
+/// Parses a term written in (a subset of) cooltt's surface syntax.
+pub fn parse(input: &str) -> Result<ConcreteSyntax<SyntaxRec>, ParseError> {
+    let mut parser = Parser {
+        tokens: lex(input)?,
+        pos: 0,
+    };
+    let tm = parser.term()?;
+    if *parser.peek() != Token::Eof {
+        return parser.error("end of input");
+    }
+    Ok(tm)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::syntax::cs;
+    use crate::termbuilder::Term;
+
+    fn parse_ok(input: &str) -> ConcreteSyntax<SyntaxRec> {
+        parse(input).unwrap_or_else(|err| panic!("{}: {}", input, err))
+    }
+
+    fn error_span(input: &str) -> Span {
+        match parse(input) {
+            Ok(tm) => panic!("{} parsed as {:?}", input, tm),
+            Err(err) => err.span,
+        }
+    }
+
+    #[test]
+    fn error_spans() {
+        assert_eq!(error_span("f $"), Span { start: 2, end: 3 });
+        assert_eq!(error_span("λ x y"), Span { start: 6, end: 6 });
+        assert_eq!(error_span("f x )"), Span { start: 4, end: 5 });
+        assert_eq!(error_span("[a b]"), Span { start: 4, end: 5 });
+        assert_eq!(error_span("(x : A)"), Span { start: 7, end: 7 });
+        assert_eq!(error_span("99999999999"), Span { start: 0, end: 11 });
+    }
+
+    #[test]
+    fn cofibration_precedence() {
+        assert_eq!(
+            parse_ok("i = 0 \\/ j = 1 /\\ k = 0"),
+            cs!(join((i = 0), (meet((j = 1), (k = 0)))))
+        );
+        assert_eq!(
+            parse_ok("i = 0 /\\ j = 1 \\/ k = 0"),
+            cs!(join((meet((i = 0), (j = 1))), (k = 0)))
+        );
+        assert_eq!(
+            parse_ok("(i = 0 \\/ j = 1) /\\ k <= j"),
+            cs!(meet((join((i = 0), (j = 1))), (k <= j)))
+        );
+        assert_eq!(parse_ok("f i = g j"), cs!((f i) = (g j)));
+        assert_eq!(parse_ok("i ∨ j ∧ k"), cs!(join(i, (meet(j, k)))));
+    }
+
+    #[test]
+    fn pairs_and_splits() {
+        assert_eq!(parse_ok("[a, b]"), cs!([a, b]));
+        assert_eq!(parse_ok("[]"), cs!(split {}));
+        assert_eq!(parse_ok("[i = 0 => a]"), cs!(split { i = 0 => a }));
+        assert_eq!(
+            parse_ok("[i = 0 => a | i = 1 => b]"),
+            cs!(split { i = 0 => a, i = 1 => b })
+        );
+        assert_eq!(
+            parse_ok("[[i = 0 => a], [j = 1 => b]]"),
+            cs!([(split { i = 0 => a }), (split { j = 1 => b })])
+        );
+    }
+
+    #[test]
+    fn telescopes() {
+        assert_eq!(parse_ok("(x y : A) → B"), cs!((x y : A) -> B));
+        assert_eq!(parse_ok("(x : A) × B x"), cs!((x : A) * (B x)));
+        assert_eq!(
+            parse_ok("(x : A) (y : B) → C"),
+            Pi(
+                vec![
+                    Cell {
+                        names: vec![Ident::User(vec!["x".to_string()])],
+                        tp: SyntaxRec::new(cs!(A)),
+                    },
+                    Cell {
+                        names: vec![Ident::User(vec!["y".to_string()])],
+                        tp: SyntaxRec::new(cs!(B)),
+                    },
+                ],
+                SyntaxRec::new(cs!(C)),
+            )
+        );
+        assert_eq!(parse_ok("A -> B -> C"), cs!(A -> (B -> C)));
+        assert_eq!(parse_ok("(A) → B"), cs!(A -> B));
+        assert_eq!(parse_ok("A * B * C"), cs!(A * (B * C)));
+    }
+
+    #[test]
+    fn print_parse_round_trips() {
+        for cs in [
+            cs!(hcom 0 1 (lam j => split { j = 0 => ?, i = 0 => ?cap, i = 1 => f i j })),
+            cs!(hfill 1 (lam k => split { k = 1 => !, join((i = 0), (i = 1)) => _ })),
+            cs!(let x := (fst p) in (snd p) x),
+            cs!((x y : dim) -> (A : type) * (A -> A)),
+            cs!(coe (lam i => A i) 0 1 a),
+            cs!(lam f => f [a, (g b)]),
+        ] {
+            let printed = Term::from_syntax(cs.clone()).0.to_string();
+            assert_eq!(parse_ok(&printed), cs, "{} did not reparse", printed);
+        }
+    }
+}
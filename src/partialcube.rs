@@ -1 +1,202 @@
+use glium::*;
+use nalgebra::{Matrix4, Similarity3, Vector3};
 
+use crate::cof::{self, CofError};
+use crate::cube::Face;
+use crate::linalg;
+use crate::syntax::SyntaxRef;
+use crate::termbuilder::Term;
+use crate::vertex::Vertex;
+
+// A cell of the cube: the dimensions that vary along it, and the projected positions of its corners.
+// Corners are indexed by the bits of the varying dimensions, in order.
+struct CubeCell {
+    dims: Vec<(String, bool)>,
+    corners: Vec<Vector3<f32>>,
+}
+
+// Enumerate all of the k-cells of the cube spanned by 'dim_names'.
+fn cells(dim_names: &[String], k: u32, size: f32) -> Vec<CubeCell> {
+    let dim = dim_names.len() as u32;
+    let mut cells = Vec::new();
+
+    // Much like 'Cube::new', we represent both the choice of varying dimensions and
+    // the position of the cell as bitsets over the dimensions.
+    for varying in (0..2_u32.pow(dim)).filter(|v| v.count_ones() == k) {
+        let varying_ixs: Vec<u32> = (0..dim).filter(|i| varying & (1 << i) != 0).collect();
+        for loc in (0..2_u32.pow(dim)).filter(|loc| loc & varying == 0) {
+            let dims = (0..dim)
+                .filter(|i| varying & (1 << i) == 0)
+                .map(|i| (dim_names[i as usize].clone(), loc & (1 << i) != 0))
+                .collect();
+
+            let corners = (0..2_u32.pow(k))
+                .map(|corner| {
+                    let mut bits = loc;
+                    for (j, i) in varying_ixs.iter().enumerate() {
+                        if corner & (1 << j) != 0 {
+                            bits |= 1 << i;
+                        }
+                    }
+                    let v: Vec<f32> = (0..dim)
+                        .map(|i| if bits & (1 << i) == 0 { -size } else { size })
+                        .collect();
+                    linalg::project(&v)
+                })
+                .collect();
+
+            cells.push(CubeCell { dims, corners });
+        }
+    }
+    cells
+}
+
+/// The part of a cube where a cofibration holds, like the open box of an 'hcom'.
+///
+/// We draw every edge that lies within the cofibration, and shade every 2-face that does.
+/// Isolated vertices (say, from 'i = 0 /\ j = 0' on a square) are not drawn.
+pub struct PartialCube {
+    // The shaded faces after projection.
+    pub faces: Vec<Face>,
+
+    pub model: Similarity3<f32>,
+
+    pub vbo: VertexBuffer<Vertex>,
+    pub face_vbo: VertexBuffer<Vertex>,
+}
+
+impl PartialCube {
+    pub fn new(
+        display: &Display,
+        dim_names: &[String],
+        size: f32,
+        color: [f32; 4],
+        term: &Term,
+        cof: SyntaxRef,
+    ) -> Result<PartialCube, CofError> {
+        let holds = |cell: &CubeCell| -> Result<bool, CofError> {
+            Ok(cof::eval(term, cof, &cof::cell_env(&cell.dims))? == Some(true))
+        };
+
+        let mut edge_geometry = Vec::new();
+        for edge in cells(dim_names, 1, size) {
+            if holds(&edge)? {
+                edge_geometry.push(Vertex::new(edge.corners[0], color));
+                edge_geometry.push(Vertex::new(edge.corners[1], color));
+            }
+        }
+
+        let mut faces = Vec::new();
+        for square in cells(dim_names, 2, size) {
+            if holds(&square)? {
+                let points = [
+                    square.corners[0],
+                    square.corners[1],
+                    square.corners[2],
+                    square.corners[3],
+                ];
+                let horiz = points[1] - points[0];
+                let vert = points[2] - points[0];
+                faces.push(Face {
+                    points,
+                    normal: horiz.cross(&vert),
+                    dims: square.dims,
+                });
+            }
+        }
+
+        let shade = [color[0], color[1], color[2], color[3] * 0.25];
+        let face_geometry: Vec<Vertex> = faces
+            .iter()
+            .flat_map(|face| {
+                vec![
+                    Vertex::new(face.points[0], shade),
+                    Vertex::new(face.points[2], shade),
+                    Vertex::new(face.points[1], shade),
+                    Vertex::new(face.points[2], shade),
+                    Vertex::new(face.points[3], shade),
+                    Vertex::new(face.points[1], shade),
+                ]
+            })
+            .collect();
+
+        let vbo = VertexBuffer::dynamic(display, &edge_geometry).unwrap();
+        let face_vbo = VertexBuffer::dynamic(display, &face_geometry).unwrap();
+        Ok(PartialCube {
+            faces,
+            model: Similarity3::identity(),
+            vbo,
+            face_vbo,
+        })
+    }
+
+    pub fn render<S: Surface>(&self, view_proj: Matrix4<f32>, shader: &Program, target: &mut S) {
+        let view_proj_unif: [[f32; 4]; 4] = view_proj.into();
+        let model_unif: [[f32; 4]; 4] = self.model.to_homogeneous().into();
+        let uniforms = uniform! {
+            model: model_unif,
+            view_projection: view_proj_unif
+        };
+        let draw_params = DrawParameters {
+            blend: Blend::alpha_blending(),
+            line_width: Some(4_f32),
+            ..Default::default()
+        };
+        target
+            .draw(
+                &self.face_vbo,
+                index::NoIndices(index::PrimitiveType::TrianglesList),
+                shader,
+                &uniforms,
+                &draw_params,
+            )
+            .unwrap();
+        target
+            .draw(
+                &self.vbo,
+                index::NoIndices(index::PrimitiveType::LinesList),
+                shader,
+                &uniforms,
+                &draw_params,
+            )
+            .unwrap();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dims(names: &[&str]) -> Vec<String> {
+        names.iter().map(|nm| nm.to_string()).collect()
+    }
+
+    #[test]
+    fn cell_counts() {
+        let cube = dims(&["i", "j", "k"]);
+        let counts: Vec<usize> = (0..=3).map(|k| cells(&cube, k, 1.0).len()).collect();
+        assert_eq!(counts, vec![8, 12, 6, 1]);
+    }
+
+    #[test]
+    fn square_corners() {
+        let cube = dims(&["i", "j", "k"]);
+        let squares = cells(&cube, 2, 1.0);
+        assert!(squares.iter().all(|cell| cell.dims.len() == 1));
+        let bottom = squares
+            .iter()
+            .find(|cell| cell.dims == vec![("k".to_string(), false)])
+            .expect("Missing the square k = 0");
+        // The first varying dimension, 'i', is the low bit of the corner index.
+        let corners: Vec<Vector3<f32>> = [
+            [-1.0, -1.0, -1.0],
+            [1.0, -1.0, -1.0],
+            [-1.0, 1.0, -1.0],
+            [1.0, 1.0, -1.0],
+        ]
+        .iter()
+        .map(|v| linalg::project(v))
+        .collect();
+        assert_eq!(bottom.corners, corners);
+    }
+}
@@ -0,0 +1,359 @@
+use std::fmt;
+
+use slotmap::SlotMap;
+
+use crate::syntax::{Cell, ConcreteSyntax, ConcreteSyntax::*, Hole, Ident, SyntaxRef};
+
+// Binding strength of each syntactic form, from loosest to tightest.
+// A subterm gets parenthesized when it binds looser than its position requires.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Prec {
+    Binder,
+    Arrow,
+    Prod,
+    Join,
+    Meet,
+    Cof,
+    Ap,
+    Atom,
+}
+
+/// Renders a subterm of a term as cooltt surface syntax.
+pub struct Pretty<'a> {
+    map: &'a SlotMap<SyntaxRef, ConcreteSyntax<SyntaxRef>>,
+    root: SyntaxRef,
+}
+
+impl<'a> Pretty<'a> {
+    pub fn new(map: &'a SlotMap<SyntaxRef, ConcreteSyntax<SyntaxRef>>, root: SyntaxRef) -> Self {
+        Pretty { map, root }
+    }
+
+    // Is this telescope a single anonymous cell, ie. is it a plain arrow or product?
+    fn is_nondependent(cells: &[Cell<SyntaxRef>]) -> bool {
+        matches!(cells, [cell] if matches!(cell.names.as_slice(), [Ident::Anon]))
+    }
+
+    fn prec(&self, cs: &ConcreteSyntax<SyntaxRef>) -> Prec {
+        match cs {
+            Pi(cells, _) if Pretty::is_nondependent(cells) => Prec::Arrow,
+            Sg(cells, _) if Pretty::is_nondependent(cells) => Prec::Prod,
+            Lam(_, _) | Let(_, _, _) | Pi(_, _) | Sg(_, _) | Ext(_, _, _) => Prec::Binder,
+            Join(vs) | Meet(vs) if vs.len() == 1 => self.prec(&self.map[vs[0]]),
+            Join(vs) if vs.len() > 1 => Prec::Join,
+            Meet(vs) if vs.len() > 1 => Prec::Meet,
+            CofEq(_, _) | CofLe(_, _) => Prec::Cof,
+            Ap(_, _)
+            | Fst(_)
+            | Snd(_)
+            | HComChk(_, _, _)
+            | HFillChk(_, _)
+            | Com(_, _, _, _, _)
+            | Coe(_, _, _, _)
+            | V(_, _, _, _)
+            | VProj(_) => Prec::Ap,
+            _ => Prec::Atom,
+        }
+    }
+
+    fn fmt_prec(&self, f: &mut fmt::Formatter, sref: SyntaxRef, prec: Prec) -> fmt::Result {
+        let cs = &self.map[sref];
+        if self.prec(cs) < prec {
+            write!(f, "(")?;
+            self.fmt_syntax(f, cs)?;
+            write!(f, ")")
+        } else {
+            self.fmt_syntax(f, cs)
+        }
+    }
+
+    fn fmt_sep(
+        &self,
+        f: &mut fmt::Formatter,
+        srefs: &[SyntaxRef],
+        sep: &str,
+        prec: Prec,
+    ) -> fmt::Result {
+        for (i, sref) in srefs.iter().enumerate() {
+            if i > 0 {
+                write!(f, "{}", sep)?;
+            }
+            self.fmt_prec(f, *sref, prec)?;
+        }
+        Ok(())
+    }
+
+    fn fmt_telescope(
+        &self,
+        f: &mut fmt::Formatter,
+        cells: &[Cell<SyntaxRef>],
+        sep: &str,
+        sref: SyntaxRef,
+    ) -> fmt::Result {
+        for cell in cells {
+            write!(f, "(")?;
+            for id in &cell.names {
+                write!(f, "{} ", id)?;
+            }
+            write!(f, ": ")?;
+            self.fmt_prec(f, cell.tp, Prec::Binder)?;
+            write!(f, ") ")?;
+        }
+        write!(f, "{} ", sep)?;
+        self.fmt_prec(f, sref, Prec::Binder)
+    }
+
+    fn fmt_branches(
+        &self,
+        f: &mut fmt::Formatter,
+        branches: &[(SyntaxRef, SyntaxRef)],
+    ) -> fmt::Result {
+        write!(f, "[")?;
+        for (i, (sref1, sref2)) in branches.iter().enumerate() {
+            write!(f, "{}", if i > 0 { " | " } else { " " })?;
+            self.fmt_prec(f, *sref1, Prec::Join)?;
+            write!(f, " => ")?;
+            self.fmt_prec(f, *sref2, Prec::Binder)?;
+        }
+        write!(f, " ]")
+    }
+
+    fn fmt_atoms(&self, f: &mut fmt::Formatter, head: &str, srefs: &[SyntaxRef]) -> fmt::Result {
+        write!(f, "{}", head)?;
+        for sref in srefs {
+            write!(f, " ")?;
+            self.fmt_prec(f, *sref, Prec::Atom)?;
+        }
+        Ok(())
+    }
+
+    fn fmt_syntax(&self, f: &mut fmt::Formatter, cs: &ConcreteSyntax<SyntaxRef>) -> fmt::Result {
+        match cs {
+            Var(id) => write!(f, "{}", id),
+            Let(sref1, id, sref2) => {
+                write!(f, "let {} := ", id)?;
+                self.fmt_prec(f, *sref1, Prec::Binder)?;
+                write!(f, " in ")?;
+                self.fmt_prec(f, *sref2, Prec::Binder)
+            }
+            Lit(n) => write!(f, "{}", n),
+            Lam(vi, sref) => {
+                write!(f, "λ")?;
+                for id in vi {
+                    write!(f, " {}", id)?;
+                }
+                write!(f, " => ")?;
+                self.fmt_prec(f, *sref, Prec::Binder)
+            }
+            // The keyword forms take a fixed number of arguments, so anything but an atom has to
+            // be parenthesized in head position, eg. '(hcom 0 1 {u}) x'.
+            Ap(sref, vsref) => {
+                self.fmt_prec(f, *sref, Prec::Atom)?;
+                for arg in vsref {
+                    write!(f, " ")?;
+                    self.fmt_prec(f, *arg, Prec::Atom)?;
+                }
+                Ok(())
+            }
+            Pi(cells, sref) if Pretty::is_nondependent(cells) => {
+                self.fmt_prec(f, cells[0].tp, Prec::Prod)?;
+                write!(f, " → ")?;
+                self.fmt_prec(f, *sref, Prec::Binder)
+            }
+            Pi(cells, sref) => self.fmt_telescope(f, cells, "→", *sref),
+            Sg(cells, sref) if Pretty::is_nondependent(cells) => {
+                self.fmt_prec(f, cells[0].tp, Prec::Join)?;
+                write!(f, " × ")?;
+                self.fmt_prec(f, *sref, Prec::Prod)
+            }
+            Sg(cells, sref) => self.fmt_telescope(f, cells, "×", *sref),
+            Pair(sref1, sref2) => {
+                write!(f, "[")?;
+                self.fmt_prec(f, *sref1, Prec::Binder)?;
+                write!(f, ", ")?;
+                self.fmt_prec(f, *sref2, Prec::Binder)?;
+                write!(f, "]")
+            }
+            Fst(sref) => self.fmt_atoms(f, "fst", &[*sref]),
+            Snd(sref) => self.fmt_atoms(f, "snd", &[*sref]),
+            Proj(sref, id) => {
+                // 'p.x', 'p.x.y' and '?h.x' would lex as qualified names (or a hole named 'h.x'),
+                // so projections out of names, holes and other projections get parenthesized.
+                match self.map[*sref] {
+                    Var(_) | Proj(_, _) | Hole(_) => {
+                        write!(f, "(")?;
+                        self.fmt_syntax(f, &self.map[*sref])?;
+                        write!(f, ")")?;
+                    }
+                    _ => self.fmt_prec(f, *sref, Prec::Atom)?,
+                }
+                write!(f, ".{}", id)
+            }
+            Type => write!(f, "type"),
+            Hole(h) => write!(f, "{}", h),
+            Underscore => write!(f, "_"),
+            Dim => write!(f, "dim"),
+            Cof => write!(f, "cof"),
+            CofEq(sref1, sref2) => {
+                self.fmt_prec(f, *sref1, Prec::Ap)?;
+                write!(f, " = ")?;
+                self.fmt_prec(f, *sref2, Prec::Ap)
+            }
+            CofLe(sref1, sref2) => {
+                self.fmt_prec(f, *sref1, Prec::Ap)?;
+                write!(f, " <= ")?;
+                self.fmt_prec(f, *sref2, Prec::Ap)
+            }
+            // Nullary joins and meets are just the constant cofibrations, and unary ones
+            // are their only disjunct/conjunct.
+            Join(vsref) => match vsref.as_slice() {
+                [] => write!(f, "#f"),
+                [sref] => self.fmt_syntax(f, &self.map[*sref]),
+                _ => self.fmt_sep(f, vsref, " \\/ ", Prec::Meet),
+            },
+            Meet(vsref) => match vsref.as_slice() {
+                [] => write!(f, "#t"),
+                [sref] => self.fmt_syntax(f, &self.map[*sref]),
+                _ => self.fmt_sep(f, vsref, " /\\ ", Prec::Cof),
+            },
+            CofSplit(vsrefp) => self.fmt_branches(f, vsrefp),
+            TopC => write!(f, "#t"),
+            BotC => write!(f, "#f"),
+            HComChk(sref1, sref2, sref3) => {
+                write!(f, "hcom ")?;
+                self.fmt_prec(f, *sref1, Prec::Atom)?;
+                write!(f, " ")?;
+                self.fmt_prec(f, *sref2, Prec::Atom)?;
+                write!(f, " {{")?;
+                self.fmt_prec(f, *sref3, Prec::Binder)?;
+                write!(f, "}}")
+            }
+            HFillChk(sref1, sref2) => {
+                write!(f, "hfill ")?;
+                self.fmt_prec(f, *sref1, Prec::Atom)?;
+                write!(f, " {{")?;
+                self.fmt_prec(f, *sref2, Prec::Binder)?;
+                write!(f, "}}")
+            }
+            Com(sref1, sref2, sref3, sref4, sref5) => {
+                self.fmt_atoms(f, "com", &[*sref1, *sref2, *sref3, *sref4])?;
+                write!(f, " {{")?;
+                self.fmt_prec(f, *sref5, Prec::Binder)?;
+                write!(f, "}}")
+            }
+            Coe(sref1, sref2, sref3, sref4) => {
+                self.fmt_atoms(f, "coe", &[*sref1, *sref2, *sref3, *sref4])
+            }
+            Ext(vi, sref, vsrefp) => {
+                write!(f, "ext")?;
+                for id in vi {
+                    write!(f, " {}", id)?;
+                }
+                write!(f, " => ")?;
+                self.fmt_prec(f, *sref, Prec::Binder)?;
+                write!(f, " with ")?;
+                self.fmt_branches(f, vsrefp)
+            }
+            V(sref1, sref2, sref3, sref4) => {
+                self.fmt_atoms(f, "V", &[*sref1, *sref2, *sref3, *sref4])
+            }
+            VProj(sref) => self.fmt_atoms(f, "vproj", &[*sref]),
+        }
+    }
+}
+
+impl<'a> fmt::Display for Pretty<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.fmt_prec(f, self.root, Prec::Binder)
+    }
+}
+
+impl fmt::Display for Ident {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Ident::Anon => write!(f, "_"),
+            Ident::User(parts) => write!(f, "{}", parts.join(".")),
+            Ident::Machine(name) => write!(f, "{}", name),
+        }
+    }
+}
+
+impl fmt::Display for Hole {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let sigil = if self.silent { "!" } else { "?" };
+        match &self.name {
+            Some(name) => write!(f, "{}{}", sigil, name),
+            None => write!(f, "{}", sigil),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::parse;
+    use crate::syntax::{cs, ConcreteSyntax, ConcreteSyntax::*, Ident, SyntaxRec};
+    use crate::termbuilder::Term;
+
+    fn print(cs: ConcreteSyntax<SyntaxRec>) -> String {
+        Term::from_syntax(cs).0.to_string()
+    }
+
+    /// Printing and then parsing should give back exactly the same tree.
+    fn assert_reparses(cs: ConcreteSyntax<SyntaxRec>) {
+        let printed = print(cs.clone());
+        let reparsed = parse(&printed).unwrap_or_else(|err| panic!("{}: {}", printed, err));
+        assert_eq!(cs, reparsed, "{} did not reparse to the same term", printed);
+    }
+
+    #[test]
+    fn applied_compositions() {
+        assert_eq!(print(cs!((hcom 0 1 u) x)), "(hcom 0 1 {u}) x");
+        assert_reparses(cs!((hcom 0 1 u) x));
+        assert_reparses(cs!((hfill 0 (lam i => ?)) x y));
+    }
+
+    fn proj(cs: ConcreteSyntax<SyntaxRec>, field: &str) -> ConcreteSyntax<SyntaxRec> {
+        Proj(SyntaxRec::new(cs), Ident::User(vec![field.to_string()]))
+    }
+
+    #[test]
+    fn applied_keyword_forms() {
+        assert_eq!(print(cs!((fst p) x)), "(fst p) x");
+        assert_reparses(cs!((fst p) x));
+        assert_reparses(cs!((snd p) x));
+        assert_reparses(cs!((coe A 0 1 a) b));
+        assert_reparses(cs!((com A 0 1 top u) b));
+        assert_reparses(Ap(
+            SyntaxRec::new(V(
+                SyntaxRec::new(cs!(r)),
+                SyntaxRec::new(cs!(A)),
+                SyntaxRec::new(cs!(B)),
+                SyntaxRec::new(cs!(e)),
+            )),
+            vec![SyntaxRec::new(cs!(x))],
+        ));
+        assert_reparses(Ap(
+            SyntaxRec::new(VProj(SyntaxRec::new(cs!(v)))),
+            vec![SyntaxRec::new(cs!(x))],
+        ));
+    }
+
+    #[test]
+    fn projections() {
+        assert_eq!(print(proj(cs!(p), "x")), "(p).x");
+        assert_reparses(proj(cs!(p), "x"));
+        assert_reparses(proj(proj(cs!(p), "x"), "y"));
+        assert_reparses(proj(cs!(f a), "x"));
+        assert_reparses(proj(cs!([a, b]), "x"));
+        assert_eq!(print(proj(cs!(?h), "x")), "(?h).x");
+        assert_reparses(proj(cs!(?h), "x"));
+        assert_reparses(proj(cs!(?), "x"));
+    }
+
+    #[test]
+    fn nested_applications() {
+        assert_eq!(print(cs!((f x) y)), "(f x) y");
+        assert_reparses(cs!((f x) y));
+        assert_reparses(cs!(f (g x) y));
+    }
+}
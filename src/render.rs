@@ -6,15 +6,36 @@ use glium::*;
 use imgui::*;
 use imgui_glium_renderer::{Renderer, Texture};
 use nalgebra::{Perspective3, Unit};
+use std::path::Path;
 use std::rc::Rc;
 
 use crate::camera;
+use crate::cof::{self, CofError};
+use crate::coverage;
 use crate::cube;
+use crate::diff::{self, Change};
+use crate::history::History;
 use crate::label;
 use crate::messages;
-use crate::messages::CubeMessage;
+use crate::messages::{CubeMessage, Outbound};
+use crate::partialcube::PartialCube;
+use crate::scope;
+use crate::script::{Script, Step};
+use crate::server::Server;
+use crate::skeleton::{self, Direction};
+use crate::subst;
+use crate::syntax::{ConcreteSyntax, Ident, SyntaxRec, SyntaxRef};
+use crate::tactic::ChkTactic;
+use crate::termbuilder::{HoleTarget, Term, TermError};
 use crate::{linalg, system};
 
+// We listen for goals on 'PORT', and send our replies to cooltt on 'COOLTT_PORT'.
+const PORT: u16 = 3001;
+const COOLTT_PORT: u16 = 3002;
+
+// Where tactic scripts are saved to and loaded from.
+const SCRIPT_FILE: &str = "six-eyes-script.json";
+
 pub struct LabeledCube {
     name: String,
     cube: cube::Cube,
@@ -23,13 +44,35 @@ pub struct LabeledCube {
     texture_id: Option<TextureId>,
 }
 
+// The filler of the composition we are working on, drawn as a cube with one more dimension than
+// the goal: the line of the composition.
+struct FillerView {
+    cube: cube::Cube,
+    open_box: PartialCube,
+    lid: PartialCube,
+}
+
 pub struct Scene {
     camera: camera::Camera,
 
     main_cube: LabeledCube,
+    // The shape of the composition problem we are currently working on, if any.
+    tube: Option<PartialCube>,
+    filler: Option<FillerView>,
+    show_filler: bool,
+    // The branch of the outermost split whose faces are highlighted.
+    selected_branch: Option<usize>,
 
     program: glium::Program,
 
+    // The goal we are currently working on, and the term we are building to fill it.
+    hole: Option<String>,
+    history: History,
+    // The tactics behind the history, so the term can be rebuilt later.
+    script: Script,
+    // How the last edit changed the term.
+    changes: Vec<String>,
+
     context: String,
     sidebar_cubes: Vec<LabeledCube>,
 }
@@ -175,6 +218,14 @@ fn init_scene(display: &Display, msg: &messages::DisplayGoal, renderer: &mut Ren
             dims: msg.dims.clone(),
             texture_id: None,
         },
+        tube: None,
+        filler: None,
+        show_filler: false,
+        selected_branch: None,
+        hole: msg.hole.clone(),
+        history: History::new(Term::new()),
+        script: Script::new(&msg.dims),
+        changes: Vec::new(),
         context: msg.context.clone(),
         sidebar_cubes: Vec::new(),
     };
@@ -184,9 +235,59 @@ fn init_scene(display: &Display, msg: &messages::DisplayGoal, renderer: &mut Ren
     scene
 }
 
-fn render_cube<S: Surface>(ui: &Ui, scene: &mut Scene, target: &mut S) {
-    render_cube_labelless(ui.io().display_size, scene, CubeIndex::MainCube, target);
+/// Draw the part of the cube spanned by 'dims' where a cofibration holds, on top of the main cube.
+pub fn set_tube(
+    display: &Display,
+    scene: &mut Scene,
+    dims: &[String],
+    term: &Term,
+    cof: SyntaxRef,
+) {
+    let blue = [0.0, 0.3, 1.0, 1.0];
+    match PartialCube::new(display, dims, 1.0, blue, term, cof) {
+        Ok(tube) => scene.tube = Some(tube),
+        Err(err) => println!("[WARN] Could not draw tube: {:?}", err),
+    }
+}
+
+/// Draw the filler of a composition along 'line' over the cube spanned by 'dims', with its lid
+/// (the result of the 'hcom') picked out.
+pub fn set_filler(
+    display: &Display,
+    scene: &mut Scene,
+    dims: &[String],
+    line: &Ident,
+    dir: Direction,
+) {
+    // Cubes only know their dimensions by name, so the line has to be a user name here.
+    let axis = Ident::User(vec![line.to_string()]);
+    let mut filler_dims = dims.to_vec();
+    filler_dims.push(line.to_string());
 
+    let (open_box, open_box_root) = Term::from_syntax(skeleton::open_box(dims, &axis, dir));
+    let (lid, lid_root) = Term::from_syntax(skeleton::lid(&axis, dir));
+
+    let black = [0.0, 0.0, 0.0, 1.0];
+    let blue = [0.0, 0.3, 1.0, 1.0];
+    let orange = [1.0, 0.5, 0.0, 1.0];
+    let built = PartialCube::new(display, &filler_dims, 1.0, blue, &open_box, open_box_root)
+        .and_then(|open_box_cube| {
+            let lid_cube = PartialCube::new(display, &filler_dims, 1.0, orange, &lid, lid_root)?;
+            Ok((open_box_cube, lid_cube))
+        });
+    match built {
+        Ok((open_box, lid)) => {
+            scene.filler = Some(FillerView {
+                cube: cube::Cube::new(display, &filler_dims, 1.0, black),
+                open_box,
+                lid,
+            })
+        }
+        Err(err) => println!("[WARN] Could not draw filler: {:?}", err),
+    }
+}
+
+fn render_cube<S: Surface>(ui: &Ui, scene: &mut Scene, target: &mut S) {
     let [width, height] = ui.io().display_size;
 
     let eye = scene.camera.eye();
@@ -197,8 +298,43 @@ fn render_cube<S: Surface>(ui: &Ui, scene: &mut Scene, target: &mut S) {
     let projection = Perspective3::new(aspect, fov, 0.1, 100.0);
 
     let view_proj = projection.to_homogeneous() * view.to_homogeneous();
+
+    if let (true, Some(filler)) = (scene.show_filler, &scene.filler) {
+        filler.cube.render(view_proj, &scene.program, target);
+        filler.open_box.render(view_proj, &scene.program, target);
+        filler.lid.render(view_proj, &scene.program, target);
+        return;
+    }
+
+    render_cube_labelless(ui.io().display_size, scene, CubeIndex::MainCube, target);
     let mvp = view_proj * scene.main_cube.cube.model.to_homogeneous();
 
+    if let Some(tube) = &scene.tube {
+        tube.render(view_proj, &scene.program, target);
+    }
+
+    let term = scene.history.term();
+    if let (Some(i), Some((split, _))) = (scene.selected_branch, outermost_split(term)) {
+        let faces = cof::branch_faces(term, split, &scene.main_cube.cube.faces).map(|branches| {
+            let faces = branches.get(i).cloned().unwrap_or_default();
+            faces.into_iter().cloned().collect::<Vec<_>>()
+        });
+        match faces {
+            Ok(faces) => {
+                for face in &faces {
+                    scene
+                        .main_cube
+                        .cube
+                        .render_face(face, view_proj, &scene.program, target);
+                }
+            }
+            Err(err) => {
+                println!("[WARN] Could not highlight branch: {:?}", err);
+                scene.selected_branch = None;
+            }
+        }
+    }
+
     for lbl in &scene.main_cube.labels {
         lbl.render(mvp, ui);
     }
@@ -213,18 +349,143 @@ fn render_cube<S: Surface>(ui: &Ui, scene: &mut Scene, target: &mut S) {
             .main_cube
             .cube
             .render_face(face, view_proj, &scene.program, target);
+        // Along with the face, show what the term looks like when restricted to it.
+        let term = scene.history.term();
+        let (mut restricted, root) = Term::from_syntax(term.rerecurse(term.root()));
+        let on_face = subst::subst(&mut restricted, root, &cof::cell_env(&face.dims));
         ui.tooltip(|| {
-            let mut s = String::new();
-            for (nm, d) in &face.dims {
-                s.push_str(&format!("{} = {}\n", nm, if *d { 1 } else { 0 }));
-            }
-            ui.text(s);
+            ui.text(print_syntax(face.cofibration()));
+            ui.text_wrapped(print_syntax(*on_face.value));
         });
     };
 }
 
-fn render_frame(ui: &Ui, scene: &mut Scene, target: &mut Frame) {
-    let [_, height] = ui.io().display_size;
+// Run a tactic on the next hole of the term. Compositions also get their tube drawn over the
+// main cube, along with their filler.
+fn run_tactic(display: &Display, scene: &mut Scene, tac: ChkTactic) {
+    edit(display, scene, |scene| {
+        let dims = scene.main_cube.dims.clone();
+        let plugged = tac
+            .run(&mut scene.history, &HoleTarget::Next, &dims)
+            .and_then(|sref| {
+                let term = scene.history.term();
+                term.path_of(sref).ok_or(TermError::DanglingRef(sref))
+            });
+        match plugged {
+            Ok(path) => {
+                scene.script.record(Step {
+                    path,
+                    tactic: tac.clone(),
+                });
+                true
+            }
+            Err(err) => {
+                println!("[WARN] Could not run tactic {:?}: {:?}", tac, err);
+                false
+            }
+        }
+    });
+}
+
+// Make an edit to the term, which reports whether it changed anything, and then update
+// everything we show about the term.
+fn edit<F: FnOnce(&mut Scene) -> bool>(display: &Display, scene: &mut Scene, edit: F) {
+    let mut before = Term::new();
+    let term = scene.history.term();
+    before
+        .paste(&HoleTarget::Next, term, term.root())
+        .expect("A fresh term has a hole");
+    if edit(scene) {
+        scene.changes = describe_changes(&before, scene.history.term());
+        show_composition(display, scene);
+    }
+}
+
+fn describe_changes(old: &Term, new: &Term) -> Vec<String> {
+    diff::diff(old, new)
+        .into_iter()
+        .map(|change| match change {
+            Change::Inserted(site) => format!("+ {}", new.pretty(site.sref)),
+            Change::Deleted(site) => format!("- {}", old.pretty(site.sref)),
+            Change::Changed(o, n) => format!("{} ↦ {}", old.pretty(o.sref), new.pretty(n.sref)),
+        })
+        .collect()
+}
+
+fn print_syntax(cs: ConcreteSyntax<SyntaxRec>) -> String {
+    Term::from_syntax(cs).0.to_string()
+}
+
+// The outermost split in the term, along with the cofibrations of its branches.
+fn outermost_split(term: &Term) -> Option<(SyntaxRef, Vec<SyntaxRef>)> {
+    let mut split = None;
+    term.walk(term.root(), &mut |_, sref, cs| {
+        if let (None, ConcreteSyntax::CofSplit(vp)) = (&split, cs) {
+            split = Some((sref, vp.iter().map(|(cof, _)| *cof).collect()));
+        }
+    });
+    split
+}
+
+// Draw the tube and filler of the outermost composition in the term, or clear them if the term
+// doesn't have one (anymore).
+fn show_composition(display: &Display, scene: &mut Scene) {
+    let term = scene.history.term();
+    let mut outermost = None;
+    term.walk(term.root(), &mut |_, sref, cs| {
+        if outermost.is_none()
+            && matches!(
+                cs,
+                ConcreteSyntax::HComChk(_, _, _) | ConcreteSyntax::HFillChk(_, _)
+            )
+        {
+            outermost = Some(sref);
+        }
+    });
+    let composition = outermost.and_then(|sref| skeleton::composition_of(&term.rerecurse(sref)));
+
+    scene.tube = None;
+    scene.filler = None;
+    scene.selected_branch = None;
+    if let Some((dir, line)) = composition {
+        let dims = scene.main_cube.dims.clone();
+        set_filler(display, scene, &dims, &line, dir);
+
+        let (tube, root) = Term::from_syntax(skeleton::tube(&dims));
+        set_tube(display, scene, &dims, &tube, root);
+    }
+}
+
+fn load_script(display: &Display, scene: &mut Scene) {
+    let script = match Script::load(Path::new(SCRIPT_FILE)) {
+        Ok(script) => script,
+        Err(err) => return println!("[WARN] Could not load script: {}", err),
+    };
+    if script.dims != scene.main_cube.dims {
+        return println!(
+            "[WARN] Script was recorded against a goal with dimensions {:?}",
+            script.dims
+        );
+    }
+    let mut history = History::new(Term::new());
+    match script.replay(&mut history) {
+        Ok(()) => edit(display, scene, |scene| {
+            scene.history = history;
+            scene.script = script;
+            true
+        }),
+        Err(err) => println!("[WARN] Could not replay script: {:?}", err),
+    }
+}
+
+fn render_frame(
+    display: &Display,
+    server: &Server,
+    ui: &Ui,
+    scene: &mut Scene,
+    target: &mut Frame,
+) {
+    let [width, height] = ui.io().display_size;
 
     render_cube(ui, scene, target);
 
@@ -259,9 +520,160 @@ fn render_frame(ui: &Ui, scene: &mut Scene, target: &mut Frame) {
                 }
             }
         });
+
+    let mut tactic = None;
+    Window::new("Term")
+        .position([width - 300.0, 0.0], Condition::Appearing)
+        .size([300.0, 250.0], Condition::Appearing)
+        .collapsible(false)
+        .build(ui, || {
+            ui.text_wrapped(scene.history.term().to_string());
+            ui.separator();
+            if ui.button("hcom 0 → 1") {
+                tactic = Some(ChkTactic::Hcom(Direction::Forward));
+            }
+            ui.same_line();
+            if ui.button("hcom 1 → 0") {
+                tactic = Some(ChkTactic::Hcom(Direction::Backward));
+            }
+            if ui.button("hfill 0 → 1") {
+                tactic = Some(ChkTactic::HFill(Direction::Forward));
+            }
+            ui.same_line();
+            if ui.button("hfill 1 → 0") {
+                tactic = Some(ChkTactic::HFill(Direction::Backward));
+            }
+            if scene.filler.is_some() {
+                ui.checkbox("Show filler", &mut scene.show_filler);
+            }
+            if let Some((split, cofs)) = outermost_split(scene.history.term()) {
+                ui.separator();
+                show_branches(ui, scene, split, &cofs);
+            }
+            let context: Vec<Ident> = scene
+                .main_cube
+                .dims
+                .iter()
+                .map(|dim| Ident::User(vec![dim.clone()]))
+                .collect();
+            let mut free: Vec<String> = Vec::new();
+            for (_, id) in scope::unbound(scene.history.term(), &context) {
+                let name = id.to_string();
+                if !free.contains(&name) {
+                    free.push(name);
+                }
+            }
+            if !free.is_empty() {
+                ui.text_wrapped(format!("From the context: {}", free.join(", ")));
+            }
+            if !scene.changes.is_empty() {
+                ui.separator();
+                ui.text("Last change");
+                for change in &scene.changes {
+                    ui.text_wrapped(change);
+                }
+            }
+            ui.separator();
+            ui.disabled(!scene.history.can_undo(), || {
+                if ui.button("Undo") {
+                    undo(display, scene);
+                }
+            });
+            ui.same_line();
+            ui.disabled(!scene.history.can_redo(), || {
+                if ui.button("Redo") {
+                    redo(display, scene);
+                }
+            });
+            ui.same_line();
+            if ui.button("Save script") {
+                if let Err(err) = scene.script.save(Path::new(SCRIPT_FILE)) {
+                    println!("[WARN] Could not save script: {}", err);
+                }
+            }
+            ui.same_line();
+            if ui.button("Load script") {
+                load_script(display, scene);
+            }
+            ui.same_line();
+            if ui.button("Export") {
+                match scene.script.export() {
+                    Ok(text) => {
+                        ui.set_clipboard_text(text);
+                        println!("[INFO] Copied the exported term to the clipboard");
+                    }
+                    Err(err) => println!("[WARN] Could not export script: {:?}", err),
+                }
+            }
+            ui.separator();
+            // Without a hole to fill, cooltt wouldn't know where to put the term.
+            ui.disabled(scene.hole.is_none(), || {
+                if ui.button("Send to cooltt") {
+                    let term = scene.history.term();
+                    server.send(Outbound::FillHole {
+                        hole: scene.hole.clone(),
+                        term: SyntaxRec::new(term.rerecurse(term.root())),
+                    });
+                }
+            });
+            ui.same_line();
+            if ui.button("Reload goal") {
+                let hole = scene.hole.clone();
+                server.send(Outbound::RequestGoal { hole });
+            }
+        });
+    if let Some(tac) = tactic {
+        run_tactic(display, scene, tac);
+    }
 }
 
-fn handle_input(ui: &Ui, scene: &mut Scene) {
+// List the branches of a split, so that one can be picked out on the cube, and point out the
+// faces of the cube that none of them cover.
+fn show_branches(ui: &Ui, scene: &mut Scene, split: SyntaxRef, cofs: &[SyntaxRef]) {
+    let term = scene.history.term();
+    ui.text("Branches");
+    let normal_forms: Vec<_> = cofs
+        .iter()
+        .map(|cof| cof::normalize(term, *cof).ok())
+        .collect();
+    for (i, cof) in cofs.iter().enumerate() {
+        let mut label = term.pretty(*cof).to_string();
+        let same = normal_forms[..i].iter().position(
+            |earlier| matches!((earlier, &normal_forms[i]), (Some(a), Some(b)) if a.equivalent(b)),
+        );
+        if let Some(j) = same {
+            label += &format!(" (same as branch {})", j + 1);
+        }
+        let selected = scene.selected_branch == Some(i);
+        if Selectable::new(format!("{}##{}", label, i))
+            .selected(selected)
+            .build(ui)
+        {
+            scene.selected_branch = if selected { None } else { Some(i) };
+        }
+    }
+
+    if let Some(cof) = scene.selected_branch.and_then(|i| cofs.get(i)) {
+        if let Err(CofError::NotCellular(_)) = cube::cofibration_cells(term, *cof) {
+            ui.text_wrapped("This branch is not made up of cells of the cube, so only the faces it covers entirely are shown.");
+        }
+    }
+
+    match coverage::check_split(term, split, &scene.main_cube.dims) {
+        Ok(coverage) if !coverage.is_complete() => {
+            let faces: Vec<String> = coverage
+                .uncovered
+                .iter()
+                .map(|face| print_syntax(face.cofibration()))
+                .collect();
+            ui.text_wrapped(format!("No branch covers {}", faces.join(", ")));
+        }
+        Ok(_) => (),
+        Err(err) => ui.text_wrapped(format!("Could not check coverage: {:?}", err)),
+    }
+}
+
+fn handle_input(display: &Display, ui: &Ui, scene: &mut Scene) {
     let io = ui.io();
     if !io.want_capture_mouse {
         let [delta_x, delta_y] = io.mouse_delta;
@@ -271,21 +683,70 @@ fn handle_input(ui: &Ui, scene: &mut Scene) {
         }
         scene.camera.zoom(0.1_f32 * io.mouse_wheel);
     }
+
+    if !io.want_capture_keyboard && io.key_ctrl {
+        if ui.is_key_pressed(Key::Z) && !io.key_shift {
+            undo(display, scene);
+        } else if ui.is_key_pressed(Key::Z) || ui.is_key_pressed(Key::Y) {
+            redo(display, scene);
+        }
+    }
+}
+
+fn undo(display: &Display, scene: &mut Scene) {
+    edit(display, scene, |scene| {
+        let undone = scene.history.undo();
+        if undone {
+            scene.script.undo();
+        }
+        undone
+    });
+}
+
+fn redo(display: &Display, scene: &mut Scene) {
+    edit(display, scene, |scene| {
+        let redone = scene.history.redo();
+        if redone {
+            scene.script.redo();
+        }
+        redone
+    });
+}
+
+// Is cooltt showing us the goal we are already working on? Goals are identified by their hole,
+// but a cooltt that doesn't send one gets matched on the shape of the goal instead.
+fn same_goal(scene: &Scene, goal: &messages::DisplayGoal) -> bool {
+    match (&goal.hole, &scene.hole) {
+        (Some(hole), Some(current)) => hole == current,
+        (None, None) => goal.dims == scene.main_cube.dims && goal.context == scene.context,
+        _ => false,
+    }
 }
 
 fn handle_message(
     msg: messages::Message,
     display: &Display,
+    server: &Server,
     scene: &mut Scene,
     renderer: &mut Renderer,
 ) {
     match msg {
-        messages::Message::DisplayGoal(goal) => *scene = init_scene(display, &goal, renderer),
+        messages::Message::DisplayGoal(goal) => {
+            let mut new_scene = init_scene(display, &goal, renderer);
+            // If cooltt is just showing us the same goal again, don't throw away our work on it.
+            if same_goal(scene, &goal) {
+                std::mem::swap(&mut new_scene.history, &mut scene.history);
+                std::mem::swap(&mut new_scene.script, &mut scene.script);
+            }
+            *scene = new_scene;
+            show_composition(display, scene);
+            server.send(Outbound::Ack);
+        }
     }
 }
 
 pub fn render() {
-    let mut system = system::init(3001, file!());
+    let mut system = system::init(PORT, COOLTT_PORT, file!());
     let dims = vec![
         "i".to_string(),
         "j".to_string(),
@@ -297,6 +758,7 @@ pub fn render() {
     let scene = init_scene(
         &system.display,
         &messages::DisplayGoal {
+            hole: None,
             dims,
             labels: vec![],
             context: ctx.to_string(),
@@ -305,8 +767,12 @@ pub fn render() {
         &mut system.renderer,
     );
 
-    system.main_loop(scene, handle_message, move |_, _, scene, target, ui| {
-        handle_input(ui, scene);
-        render_frame(ui, scene, target);
-    });
+    system.main_loop(
+        scene,
+        handle_message,
+        move |_, display, server, scene, target, ui| {
+            handle_input(display, ui, scene);
+            render_frame(display, server, ui, scene, target);
+        },
+    );
 }
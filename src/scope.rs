@@ -0,0 +1,212 @@
+use std::collections::HashMap;
+
+use crate::syntax::{ConcreteSyntax::*, Ident, SyntaxRef};
+use crate::termbuilder::Term;
+
+/// What a variable refers to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Binding {
+    /// One of the names bound by a 'Lam', 'Let', 'Pi', 'Sg' or 'Ext' node of the term.
+    Binder(SyntaxRef),
+    /// A variable from the context that the term lives in, eg. a dimension of the goal.
+    Context,
+    Unbound,
+}
+
+#[derive(Clone, Debug)]
+pub enum ScopeError {
+    NotABinder(SyntaxRef),
+    /// The binder does not bind the name we were asked to rename.
+    NotBound(SyntaxRef, Ident),
+    /// Renaming would change what the variable at this node refers to.
+    Capture(SyntaxRef),
+}
+
+/// Work out what every variable under 'sref' refers to. Any names bound above 'sref'
+/// are treated as though they were part of 'context'.
+pub fn resolve(term: &Term, sref: SyntaxRef, context: &[Ident]) -> HashMap<SyntaxRef, Binding> {
+    let mut bindings = HashMap::new();
+    resolve_under(term, sref, context, &mut Vec::new(), &mut bindings);
+    bindings
+}
+
+fn resolve_under(
+    term: &Term,
+    sref: SyntaxRef,
+    context: &[Ident],
+    scope: &mut Vec<(Ident, SyntaxRef)>,
+    bindings: &mut HashMap<SyntaxRef, Binding>,
+) {
+    let cs = &term.map[sref];
+    if let Var(id) = cs {
+        // The innermost binder wins.
+        let binding = match scope.iter().rev().find(|(nm, _)| nm == id) {
+            Some((_, binder)) => Binding::Binder(*binder),
+            None if context.contains(id) => Binding::Context,
+            None => Binding::Unbound,
+        };
+        bindings.insert(sref, binding);
+    }
+    for (child, bound) in cs.scoped_children() {
+        let depth = scope.len();
+        scope.extend(
+            bound
+                .into_iter()
+                .filter(|id| *id != Ident::Anon)
+                .map(|id| (id, sref)),
+        );
+        resolve_under(term, *child, context, scope, bindings);
+        scope.truncate(depth);
+    }
+}
+
+/// The variables of the term that are bound neither in the term nor in 'context', along with their names.
+pub fn unbound(term: &Term, context: &[Ident]) -> Vec<(SyntaxRef, Ident)> {
+    let bindings = resolve(term, term.root(), context);
+    let mut unbound = Vec::new();
+    term.walk(term.root(), &mut |_, sref, cs| match cs {
+        Var(id) if bindings.get(&sref) == Some(&Binding::Unbound) => {
+            unbound.push((sref, id.clone()))
+        }
+        _ => (),
+    });
+    unbound
+}
+
+/// Rename the variable 'old' bound by 'binder' to 'new', along with every occurrence of it.
+///
+/// Nothing is changed if the new name would capture, or be captured by, some other
+/// binder; the offending variable is returned instead.
+pub fn rename(
+    term: &mut Term,
+    binder: SyntaxRef,
+    old: &Ident,
+    new: &Ident,
+    context: &[Ident],
+) -> Result<(), ScopeError> {
+    let names = term.map[binder]
+        .binders_mut()
+        .ok_or(ScopeError::NotABinder(binder))?;
+    if !names.iter().any(|id| *id == old) {
+        return Err(ScopeError::NotBound(binder, old.clone()));
+    }
+    let before = resolve(term, term.root(), context);
+    let occurrences: Vec<SyntaxRef> = before
+        .iter()
+        .filter(|(sref, binding)| {
+            **binding == Binding::Binder(binder)
+                && matches!(&term.map[**sref], Var(id) if id == old)
+        })
+        .map(|(sref, _)| *sref)
+        .collect();
+
+    let saved = term.map[binder].clone();
+    for id in term.map[binder].binders_mut().into_iter().flatten() {
+        if id == old {
+            *id = new.clone();
+        }
+    }
+    for sref in &occurrences {
+        term.map[*sref] = Var(new.clone());
+    }
+
+    // Rather than predicting every way that a capture could happen, just check that
+    // every variable still points at the same thing.
+    let after = resolve(term, term.root(), context);
+    if let Some((sref, _)) = before
+        .iter()
+        .find(|(sref, binding)| after[sref] != **binding)
+    {
+        term.map[binder] = saved;
+        for occurrence in &occurrences {
+            term.map[*occurrence] = Var(old.clone());
+        }
+        return Err(ScopeError::Capture(*sref));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::syntax::{cs, ConcreteSyntax, SyntaxRec};
+
+    fn user(name: &str) -> Ident {
+        Ident::User(vec![name.to_string()])
+    }
+
+    // What each variable of the term refers to, in the order they are printed.
+    fn bindings(term: &Term, context: &[Ident]) -> Vec<(Ident, Binding)> {
+        let bindings = resolve(term, term.root(), context);
+        let mut vars = Vec::new();
+        term.walk(term.root(), &mut |_, sref, cs| {
+            if let Var(id) = cs {
+                vars.push((id.clone(), bindings[&sref]));
+            }
+        });
+        vars
+    }
+
+    fn body(term: &Term, sref: SyntaxRef) -> SyntaxRef {
+        match &term.map[sref] {
+            Lam(_, body) => *body,
+            _ => panic!("Not a lambda"),
+        }
+    }
+
+    fn print(cs: ConcreteSyntax<SyntaxRec>) -> String {
+        Term::from_syntax(cs).0.to_string()
+    }
+
+    #[test]
+    fn innermost_binder_wins() {
+        let (term, root) = Term::from_syntax(cs!(lam x => lam x => x));
+        let inner = body(&term, root);
+        assert_eq!(
+            bindings(&term, &[]),
+            vec![(user("x"), Binding::Binder(inner))]
+        );
+    }
+
+    #[test]
+    fn context_and_unbound() {
+        let (term, _) = Term::from_syntax(cs!(f i j));
+        assert_eq!(
+            bindings(&term, &[user("i")]),
+            vec![
+                (user("f"), Binding::Unbound),
+                (user("i"), Binding::Context),
+                (user("j"), Binding::Unbound),
+            ]
+        );
+        let names: Vec<Ident> = unbound(&term, &[user("i")])
+            .into_iter()
+            .map(|(_, id)| id)
+            .collect();
+        assert_eq!(names, vec![user("f"), user("j")]);
+    }
+
+    #[test]
+    fn rename_refuses_capture() {
+        let (mut term, root) = Term::from_syntax(cs!(lam i => lam j => f i j));
+        let before = term.to_string();
+        assert!(matches!(
+            rename(&mut term, root, &user("i"), &user("j"), &[]),
+            Err(ScopeError::Capture(_))
+        ));
+        assert_eq!(term.to_string(), before);
+    }
+
+    #[test]
+    fn rename_every_occurrence() {
+        let (mut term, root) = Term::from_syntax(cs!(lam i => (lam j => f i j) i));
+        rename(&mut term, root, &user("i"), &user("k"), &[]).expect("Failed to rename");
+        assert_eq!(term.to_string(), print(cs!(lam k => (lam j => f k j) k)));
+        let bound: Vec<Binding> = bindings(&term, &[])
+            .into_iter()
+            .filter(|(id, _)| *id == user("k"))
+            .map(|(_, binding)| binding)
+            .collect();
+        assert_eq!(bound, vec![Binding::Binder(root); 2]);
+    }
+}
@@ -0,0 +1,171 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::history::History;
+use crate::tactic::ChkTactic;
+use crate::termbuilder::{HoleTarget, Term, TermError};
+
+/// A tactic, along with the path to the hole it was run on.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Step {
+    pub path: Vec<usize>,
+    pub tactic: ChkTactic,
+}
+
+/// The tactics that built a term for a goal, in the order they were run.
+///
+/// This is kept in step with the undo history, so undone tactics drop out of the script
+/// until they are redone.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Script {
+    /// The dimensions of the goal that the script was recorded against.
+    pub dims: Vec<String>,
+    pub steps: Vec<Step>,
+    #[serde(skip)]
+    undone: Vec<Step>,
+}
+
+impl Script {
+    pub fn new(dims: &[String]) -> Script {
+        Script {
+            dims: dims.to_vec(),
+            ..Script::default()
+        }
+    }
+
+    pub fn record(&mut self, step: Step) {
+        self.steps.push(step);
+        self.undone.clear();
+    }
+
+    pub fn undo(&mut self) {
+        if let Some(step) = self.steps.pop() {
+            self.undone.push(step);
+        }
+    }
+
+    pub fn redo(&mut self) {
+        if let Some(step) = self.undone.pop() {
+            self.steps.push(step);
+        }
+    }
+
+    /// Run every step of the script, in order.
+    pub fn replay(&self, history: &mut History) -> Result<(), TermError> {
+        for step in &self.steps {
+            step.tactic
+                .run(history, &HoleTarget::Path(step.path.clone()), &self.dims)?;
+        }
+        Ok(())
+    }
+
+    /// The term that the script builds, as cooltt syntax.
+    pub fn export(&self) -> Result<String, TermError> {
+        let mut history = History::new(Term::new());
+        self.replay(&mut history)?;
+        Ok(history.term().to_string())
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        fs::write(path, serde_json::to_string_pretty(self)?)
+    }
+
+    pub fn load(path: &Path) -> io::Result<Script> {
+        Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::skeleton::Direction;
+
+    // Run a tactic on the next hole and record it, the way the UI does.
+    fn run(history: &mut History, script: &mut Script, tactic: ChkTactic) {
+        let sref = tactic
+            .run(history, &HoleTarget::Next, &script.dims)
+            .expect("Failed to run tactic");
+        let path = history
+            .term()
+            .path_of(sref)
+            .expect("Plugged a detached hole");
+        script.record(Step { path, tactic });
+    }
+
+    fn undo(history: &mut History, script: &mut Script) {
+        assert!(history.undo());
+        script.undo();
+    }
+
+    fn redo(history: &mut History, script: &mut Script) {
+        assert!(history.redo());
+        script.redo();
+    }
+
+    fn assert_exports(history: &History, script: &Script) {
+        let exported = script.export().expect("Failed to replay script");
+        assert_eq!(history.term().to_string(), exported);
+    }
+
+    #[test]
+    fn redone_tactics_export_the_same_names() {
+        let dims = vec!["i".to_string()];
+        let mut history = History::new(Term::new());
+        let mut script = Script::new(&dims);
+
+        run(
+            &mut history,
+            &mut script,
+            ChkTactic::Hcom(Direction::Forward),
+        );
+        undo(&mut history, &mut script);
+        run(
+            &mut history,
+            &mut script,
+            ChkTactic::Hcom(Direction::Forward),
+        );
+        assert_exports(&history, &script);
+
+        run(
+            &mut history,
+            &mut script,
+            ChkTactic::HFill(Direction::Backward),
+        );
+        undo(&mut history, &mut script);
+        undo(&mut history, &mut script);
+        redo(&mut history, &mut script);
+        assert_exports(&history, &script);
+        run(
+            &mut history,
+            &mut script,
+            ChkTactic::HFill(Direction::Backward),
+        );
+        run(&mut history, &mut script, ChkTactic::Intro(vec![]));
+        assert_exports(&history, &script);
+    }
+
+    #[test]
+    fn round_trip() {
+        let dims = vec!["i".to_string(), "j".to_string()];
+        let mut history = History::new(Term::new());
+        let mut script = Script::new(&dims);
+        run(
+            &mut history,
+            &mut script,
+            ChkTactic::HFill(Direction::Forward),
+        );
+        run(
+            &mut history,
+            &mut script,
+            ChkTactic::SilentHole(Some("cap".to_string())),
+        );
+
+        let json = serde_json::to_string(&script).expect("Failed to serialize script");
+        let loaded: Script = serde_json::from_str(&json).expect("Failed to deserialize script");
+        assert_eq!(loaded.dims, script.dims);
+        assert_eq!(loaded.steps, script.steps);
+        assert_exports(&history, &loaded);
+    }
+}
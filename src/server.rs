@@ -1,23 +1,38 @@
-use std::io::Read;
-use std::net::{TcpListener, TcpStream};
+use std::io::{self, Read, Write};
+use std::net::{Ipv4Addr, Shutdown, SocketAddr, TcpListener, TcpStream};
 use std::sync::mpsc::{self, TryRecvError};
 use std::thread::{self, JoinHandle};
+use std::time::Duration;
 
-use crate::messages::Message;
-use crate::syntax::Node;
+use crate::messages::{Message, Outbound};
 
 // FIXME: We should make sure that we shut things down properly
 #[allow(dead_code)]
 pub struct Server {
-    port: u32,
+    port: u16,
+    // Where cooltt is listening for our replies.
+    cooltt_port: u16,
     server_thread: JoinHandle<()>,
     rx: mpsc::Receiver<Message>,
+    // Replies go through their own thread, so that a slow (or absent) cooltt can't hold up rendering.
+    sender_thread: JoinHandle<()>,
+    outbox: mpsc::Sender<Outbound>,
 }
 
 impl Server {
-    pub fn init(port: u32) -> Server {
+    pub fn init(port: u16, cooltt_port: u16) -> Server {
         let (tx, rx) = mpsc::channel();
+        let (outbox, replies) = mpsc::channel::<Outbound>();
 
+        let sender_thread = thread::spawn(move || {
+            for msg in replies {
+                if let Err(err) = send_to(cooltt_port, &msg) {
+                    println!("[WARN] Could not send {:?} to cooltt: {}", msg, err);
+                }
+            }
+        });
+
+        let errors = outbox.clone();
         let server_thread = thread::spawn(move || {
             let listener = TcpListener::bind(format!("127.0.0.1:{}", port))
                 .expect("Failed to initialize server");
@@ -30,19 +45,32 @@ impl Server {
                     Result::Ok(0) => (),
                     Result::Ok(_) => match serde_json::from_str(&str) {
                         Result::Ok(msg) => tx.send(msg).expect("Could not send message."),
-                        Result::Err(err) => println!("Deserialization Error: {:?}", err),
+                        Result::Err(err) => {
+                            println!("Deserialization Error: {:?}", err);
+                            errors
+                                .send(Outbound::Error(err.to_string()))
+                                .expect("Could not send reply.");
+                        }
                     },
                     Result::Err(err) => println!("Read Error: {:?}", err),
                 }
             }
         });
 
-        Server { port, server_thread, rx }
+        Server {
+            port,
+            cooltt_port,
+            server_thread,
+            rx,
+            sender_thread,
+            outbox,
+        }
     }
 
-    pub fn send(&self, cs: Node) {
-        let stream = TcpStream::connect(format!("127.0.0.1:{}", self.port)).expect("oh no 1");
-        serde_json::to_writer(stream, &cs).expect("oh no 2");
+    /// Queue a message for cooltt. Messages are sent in order, and any that can't be delivered
+    /// are reported and dropped.
+    pub fn send(&self, msg: Outbound) {
+        self.outbox.send(msg).expect("Could not send reply.");
     }
 
     pub fn poll(&self) -> Option<Message> {
@@ -53,3 +81,16 @@ impl Server {
         }
     }
 }
+
+// How long we wait on cooltt before giving up on a message.
+const SEND_TIMEOUT: Duration = Duration::from_secs(2);
+
+// Like cooltt, we send one message per connection, and close our end to mark where it stops.
+fn send_to(port: u16, msg: &Outbound) -> io::Result<()> {
+    let addr = SocketAddr::from((Ipv4Addr::LOCALHOST, port));
+    let mut stream = TcpStream::connect_timeout(&addr, SEND_TIMEOUT)?;
+    stream.set_write_timeout(Some(SEND_TIMEOUT))?;
+    serde_json::to_writer(&mut stream, msg)?;
+    stream.flush()?;
+    stream.shutdown(Shutdown::Write)
+}
@@ -0,0 +1,224 @@
+use crate::coverage::BoundaryFace;
+use crate::syntax::{ConcreteSyntax, ConcreteSyntax::*, Hole, Ident, SyntaxRec};
+use crate::termbuilder::Term;
+
+/// Which way a composition goes along its line.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
+    /// From 0 to 1.
+    Forward,
+    /// From 1 to 0.
+    Backward,
+}
+
+impl Direction {
+    pub fn endpoints(self) -> (u32, u32) {
+        match self {
+            Direction::Forward => (0, 1),
+            Direction::Backward => (1, 0),
+        }
+    }
+
+    pub fn from_endpoints(r: u32, s: u32) -> Option<Direction> {
+        match (r, s) {
+            (0, 1) => Some(Direction::Forward),
+            (1, 0) => Some(Direction::Backward),
+            _ => None,
+        }
+    }
+}
+
+fn rec(cs: ConcreteSyntax<SyntaxRec>) -> SyntaxRec {
+    SyntaxRec::new(cs)
+}
+
+fn hole(name: String) -> SyntaxRec {
+    rec(Hole(Hole {
+        name: Some(name),
+        silent: false,
+    }))
+}
+
+/// Every codimension 1 face of the cube spanned by 'dims'.
+pub fn boundary(dims: &[String]) -> Vec<BoundaryFace> {
+    dims.iter()
+        .flat_map(|dim| {
+            [false, true].map(|side| BoundaryFace {
+                dim: dim.clone(),
+                side,
+            })
+        })
+        .collect()
+}
+
+/// The cofibration carving out the boundary of the cube spanned by 'dims', ie. the tube of a composition.
+pub fn tube(dims: &[String]) -> ConcreteSyntax<SyntaxRec> {
+    Join(
+        boundary(dims)
+            .iter()
+            .map(|face| rec(face.cofibration()))
+            .collect(),
+    )
+}
+
+// The system of a composition along 'line': a hole for the cap at 'line = r', and one for each
+// boundary face of the goal.
+fn system(line: &Ident, r: u32, dims: &[String]) -> ConcreteSyntax<SyntaxRec> {
+    let cap = (
+        rec(CofEq(rec(Var(line.clone())), rec(Lit(r)))),
+        hole("cap".to_string()),
+    );
+    let faces = boundary(dims).into_iter().map(|face| {
+        let name = format!("{}{}", face.dim, face.side as u32);
+        (rec(face.cofibration()), hole(name))
+    });
+    CofSplit(std::iter::once(cap).chain(faces).collect())
+}
+
+/// The line that an 'hcom' or 'hfill' composes along, eg. 'j' in 'hcom 0 1 {λ j => ...}'.
+pub fn line_of(cs: &ConcreteSyntax<SyntaxRec>) -> Option<Ident> {
+    let u = match cs {
+        HComChk(_, _, u) | HFillChk(_, u) => u,
+        _ => return None,
+    };
+    match &*u.value {
+        Lam(vi, _) => vi.first().cloned(),
+        _ => None,
+    }
+}
+
+/// Which way an 'hcom' or 'hfill' goes, and along which line, if it goes between endpoints.
+pub fn composition_of(cs: &ConcreteSyntax<SyntaxRec>) -> Option<(Direction, Ident)> {
+    let dir = match cs {
+        HComChk(r, s, _) => match (&*r.value, &*s.value) {
+            (Lit(r), Lit(s)) => Direction::from_endpoints(*r, *s)?,
+            _ => return None,
+        },
+        HFillChk(r, _) => match &*r.value {
+            Lit(r) if *r <= 1 => Direction::from_endpoints(*r, 1 - *r)?,
+            _ => return None,
+        },
+        _ => return None,
+    };
+    Some((dir, line_of(cs)?))
+}
+
+/// The face of the filler of a composition along 'line' where the composite lives, ie. 'line = s'.
+pub fn lid(line: &Ident, dir: Direction) -> ConcreteSyntax<SyntaxRec> {
+    let (_, s) = dir.endpoints();
+    CofEq(rec(Var(line.clone())), rec(Lit(s)))
+}
+
+/// The part of the filler of a composition along 'line' that the system specifies: the tube
+/// along with the cap at 'line = r'.
+pub fn open_box(dims: &[String], line: &Ident, dir: Direction) -> ConcreteSyntax<SyntaxRec> {
+    let (r, _) = dir.endpoints();
+    let cap = rec(CofEq(rec(Var(line.clone())), rec(Lit(r))));
+    let faces = boundary(dims)
+        .into_iter()
+        .map(|face| rec(face.cofibration()));
+    Join(faces.chain(std::iter::once(cap)).collect())
+}
+
+/// An 'hcom' for a goal whose cube is spanned by 'dims', going in direction 'dir'.
+///
+/// The line of the composition gets a fresh name from 'term', which is where the result should be plugged.
+/// The holes are named so they can be found with 'HoleTarget::Name': '?cap' for the cap, and
+/// eg. '?i0' for the face 'i = 0'.
+pub fn hcom(term: &mut Term, dims: &[String], dir: Direction) -> ConcreteSyntax<SyntaxRec> {
+    term.reserve(dims.iter().map(|dim| Ident::User(vec![dim.clone()])));
+    let line = term.fresh("j");
+    let (r, s) = dir.endpoints();
+    HComChk(
+        rec(Lit(r)),
+        rec(Lit(s)),
+        rec(Lam(vec![line.clone()], rec(system(&line, r, dims)))),
+    )
+}
+
+/// An 'hfill' for a goal whose cube is spanned by 'dims', going in direction 'dir'.
+///
+/// This is the same as 'hcom', except that we get back the whole filler rather than just its lid.
+/// The holes are named the same way.
+pub fn hfill(term: &mut Term, dims: &[String], dir: Direction) -> ConcreteSyntax<SyntaxRec> {
+    term.reserve(dims.iter().map(|dim| Ident::User(vec![dim.clone()])));
+    let line = term.fresh("j");
+    let (r, _) = dir.endpoints();
+    HFillChk(
+        rec(Lit(r)),
+        rec(Lam(vec![line.clone()], rec(system(&line, r, dims)))),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::syntax::cs;
+
+    fn dims(names: &[&str]) -> Vec<String> {
+        names.iter().map(|nm| nm.to_string()).collect()
+    }
+
+    fn hole_names(cs: ConcreteSyntax<SyntaxRec>) -> Vec<String> {
+        Term::from_syntax(cs)
+            .0
+            .holes()
+            .into_iter()
+            .map(|info| info.hole.name.expect("Anonymous hole in a skeleton"))
+            .collect()
+    }
+
+    #[test]
+    fn one_hole_per_face() {
+        let dims = dims(&["i", "k"]);
+        let skeleton = hcom(&mut Term::new(), &dims, Direction::Forward);
+        assert_eq!(
+            Term::from_syntax(skeleton.clone()).0.to_string(),
+            Term::from_syntax(cs!(hcom 0 1 (lam j => split {
+                j = 0 => ?cap, i = 0 => ?i0, i = 1 => ?i1, k = 0 => ?k0, k = 1 => ?k1
+            })))
+            .0
+            .to_string()
+        );
+        assert_eq!(hole_names(skeleton), vec!["cap", "i0", "i1", "k0", "k1"]);
+
+        let skeleton = hfill(&mut Term::new(), &dims[..1], Direction::Backward);
+        assert_eq!(hole_names(skeleton), vec!["cap", "i0", "i1"]);
+    }
+
+    #[test]
+    fn line_avoids_goal_dimensions() {
+        let dims = dims(&["i", "j"]);
+        let skeleton = hcom(&mut Term::new(), &dims, Direction::Forward);
+        assert_eq!(
+            composition_of(&skeleton),
+            Some((Direction::Forward, Ident::Machine("j1".to_string())))
+        );
+        assert_eq!(hole_names(skeleton), vec!["cap", "i0", "i1", "j0", "j1"]);
+    }
+
+    #[test]
+    fn compositions() {
+        let dims = vec!["i".to_string()];
+        let j = Ident::Machine("j".to_string());
+        for dir in [Direction::Forward, Direction::Backward] {
+            let mut term = Term::new();
+            assert_eq!(
+                composition_of(&hcom(&mut term, &dims, dir)),
+                Some((dir, j.clone()))
+            );
+            let mut term = Term::new();
+            assert_eq!(
+                composition_of(&hfill(&mut term, &dims, dir)),
+                Some((dir, j.clone()))
+            );
+        }
+        assert_eq!(composition_of(&cs!(hcom 0 0 (lam k => ?))), None);
+        assert_eq!(composition_of(&cs!(hcom 0 1 u)), None);
+        assert_eq!(composition_of(&cs!(hfill i (lam k => ?))), None);
+        assert_eq!(
+            composition_of(&cs!(hfill 1 (lam k => ?))),
+            Some((Direction::Backward, Ident::User(vec!["k".to_string()])))
+        );
+    }
+}
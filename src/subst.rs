@@ -0,0 +1,184 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::cof::{self, Dim};
+use crate::syntax::{ConcreteSyntax, ConcreteSyntax::*, Ident, SyntaxRec, SyntaxRef};
+use crate::termbuilder::Term;
+
+/// A substitution of dimensions for dimension variables, eg. 'i := 0' or 'j := i'.
+pub type Subst = HashMap<Ident, Dim>;
+
+/// Substitute dimensions into a subterm, producing a new term.
+///
+/// Binders that would capture a variable of the substitution are renamed to fresh machine names,
+/// and any cofibration that becomes decidable is replaced by '#t' or '#f'. A split with a branch
+/// that now always applies is replaced by that branch.
+pub fn subst(term: &mut Term, sref: SyntaxRef, sub: &Subst) -> SyntaxRec {
+    // The variables of the substitution may well not appear in the term yet.
+    term.reserve(sub.keys().cloned());
+    term.reserve(sub.values().filter_map(|d| match d {
+        Dim::Var(id) => Some(id.clone()),
+        _ => None,
+    }));
+    SyntaxRec::new(subst_under(term, sref, sub))
+}
+
+fn base_name(id: &Ident) -> String {
+    match id {
+        Ident::User(parts) => parts.join("_"),
+        Ident::Machine(name) => name.clone(),
+        Ident::Anon => "x".to_string(),
+    }
+}
+
+fn subst_under(term: &mut Term, sref: SyntaxRef, sub: &Subst) -> ConcreteSyntax<SyntaxRec> {
+    let cs = term.map[sref].clone();
+    if let Var(id) = &cs {
+        return match sub.get(id) {
+            Some(d) => d.to_syntax(),
+            None => Var(id.clone()),
+        };
+    }
+
+    // Rename any bound variable that a dimension we are substituting in would refer to.
+    let captured: HashSet<&Ident> = sub
+        .values()
+        .filter_map(|d| match d {
+            Dim::Var(id) => Some(id),
+            _ => None,
+        })
+        .collect();
+    let mut renaming = HashMap::new();
+    for (_, names) in cs.scoped_children() {
+        for id in names {
+            if captured.contains(&id) && !renaming.contains_key(&id) {
+                let new = term.fresh(&base_name(&id));
+                renaming.insert(id, new);
+            }
+        }
+    }
+
+    let subs: Vec<Subst> = cs
+        .scoped_children()
+        .into_iter()
+        .map(|(_, names)| {
+            let mut sub = sub.clone();
+            for id in names {
+                sub.remove(&id);
+                if let Some(new) = renaming.get(&id) {
+                    sub.insert(id, Dim::Var(new.clone()));
+                }
+            }
+            sub
+        })
+        .collect();
+    let mut subs = subs.iter();
+    let mut result = cs.map(|child| {
+        let sub = subs.next().expect("Scoped children out of sync");
+        SyntaxRec::new(subst_under(term, *child, sub))
+    });
+    for id in result.binders_mut().into_iter().flatten() {
+        if let Some(new) = renaming.get(id) {
+            *id = new.clone();
+        }
+    }
+    simplify(result)
+}
+
+fn as_dim(cs: &ConcreteSyntax<SyntaxRec>) -> Option<Dim> {
+    match cs {
+        Lit(0) => Some(Dim::Zero),
+        Lit(1) => Some(Dim::One),
+        Var(id) => Some(Dim::Var(id.clone())),
+        _ => None,
+    }
+}
+
+fn decide(cs: &ConcreteSyntax<SyntaxRec>) -> Option<bool> {
+    match cs {
+        TopC => Some(true),
+        BotC => Some(false),
+        CofEq(r, s) => cof::eval_eq(&as_dim(&r.value)?, &as_dim(&s.value)?),
+        CofLe(r, s) => cof::eval_le(&as_dim(&r.value)?, &as_dim(&s.value)?),
+        // The children have already been simplified, so they are decided iff they are constants.
+        Join(vr) if vr.iter().any(|r| matches!(*r.value, TopC)) => Some(true),
+        Join(vr) if vr.iter().all(|r| matches!(*r.value, BotC)) => Some(false),
+        Meet(vr) if vr.iter().any(|r| matches!(*r.value, BotC)) => Some(false),
+        Meet(vr) if vr.iter().all(|r| matches!(*r.value, TopC)) => Some(true),
+        _ => None,
+    }
+}
+
+// Simplify a node whose children have already been simplified.
+fn simplify(cs: ConcreteSyntax<SyntaxRec>) -> ConcreteSyntax<SyntaxRec> {
+    match cs {
+        CofEq(_, _) | CofLe(_, _) | Join(_) | Meet(_) => match decide(&cs) {
+            Some(true) => TopC,
+            Some(false) => BotC,
+            None => cs,
+        },
+        CofSplit(branches) => {
+            let branches: Vec<_> = branches
+                .into_iter()
+                .filter(|(cof, _)| !matches!(*cof.value, BotC))
+                .collect();
+            match branches
+                .iter()
+                .position(|(cof, _)| matches!(*cof.value, TopC))
+            {
+                Some(i) => *branches.into_iter().nth(i).unwrap().1.value,
+                None => CofSplit(branches),
+            }
+        }
+        _ => cs,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::syntax::cs;
+
+    fn user(name: &str) -> Ident {
+        Ident::User(vec![name.to_string()])
+    }
+
+    fn print(cs: ConcreteSyntax<SyntaxRec>) -> String {
+        Term::from_syntax(cs).0.to_string()
+    }
+
+    fn substituted(cs: ConcreteSyntax<SyntaxRec>, sub: &[(&str, Dim)]) -> String {
+        let (mut term, sref) = Term::from_syntax(cs);
+        let sub = sub.iter().map(|(x, d)| (user(x), d.clone())).collect();
+        print(*subst(&mut term, sref, &sub).value)
+    }
+
+    #[test]
+    fn binders_avoid_capture() {
+        assert_eq!(
+            substituted(
+                cs!(lam i => split { j = i => a, i = 0 => b }),
+                &[("j", Dim::Var(user("i")))]
+            ),
+            print(cs!(lam i1 => split { i = i1 => a, i1 = 0 => b }))
+        );
+    }
+
+    #[test]
+    fn decided_splits_collapse() {
+        let split = cs!(split { i = 0 => a, i = 1 => b, j = 0 => c });
+        assert_eq!(substituted(split.clone(), &[("i", Dim::Zero)]), "a");
+        assert_eq!(substituted(split.clone(), &[("i", Dim::One)]), "b");
+        assert_eq!(
+            substituted(split, &[("j", Dim::One)]),
+            print(cs!(split { i = 0 => a, i = 1 => b }))
+        );
+    }
+
+    #[test]
+    fn binders_shadow_the_substitution() {
+        assert_eq!(
+            substituted(cs!(f j (lam j => j)), &[("j", Dim::Zero)]),
+            print(cs!(f 0 (lam j => j)))
+        );
+    }
+}
@@ -1,8 +1,15 @@
-use serde::{ser::SerializeSeq, *};
+use serde::{
+    de::{Expected, SeqAccess, Visitor},
+    ser::SerializeSeq,
+    *,
+};
 use slotmap::*;
+use std::convert::Infallible;
+use std::fmt;
+use std::marker::PhantomData;
 use std::rc::Rc;
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub enum Ident {
     Anon,
     User(Vec<String>),
@@ -13,18 +20,31 @@ new_key_type! {
     pub struct SyntaxRef;
 }
 
-#[derive(Clone, Debug, Serialize)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Hole {
     pub name: Option<String>,
     pub silent: bool,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Cell<Rec> {
+    pub names: Vec<Ident>,
+    pub tp: Rec,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub enum ConcreteSyntax<Rec> {
     Var(Ident),
+    Let(Rec, Ident, Rec),
     Lit(u32),
     Lam(Vec<Ident>, Rec),
     Ap(Rec, Vec<Rec>),
+    Pi(Vec<Cell<Rec>>, Rec),
+    Sg(Vec<Cell<Rec>>, Rec),
+    Pair(Rec, Rec),
+    Fst(Rec),
+    Snd(Rec),
+    Proj(Rec, Ident),
     Type,
     Hole(Hole),
     Underscore,
@@ -39,8 +59,16 @@ pub enum ConcreteSyntax<Rec> {
     BotC,
     HComChk(Rec, Rec, Rec),
     HFillChk(Rec, Rec),
+    Com(Rec, Rec, Rec, Rec, Rec),
+    Coe(Rec, Rec, Rec, Rec),
+    // There is no dedicated path type former: cooltt defines 'path' in its prelude as
+    // an extension type with boundary [i=0 => a | i=1 => b].
+    Ext(Vec<Ident>, Rec, Vec<(Rec, Rec)>),
+    V(Rec, Rec, Rec, Rec),
+    VProj(Rec),
 }
 
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct SyntaxRec {
     pub value: Box<ConcreteSyntax<SyntaxRec>>,
 }
@@ -66,6 +94,366 @@ impl SyntaxRec {
     }
 }
 
+/// Build a 'ConcreteSyntax<SyntaxRec>' out of something that looks a lot like cooltt, eg.
+///
+/// ```
+/// use six_eyes::{cs, syntax::ConcreteSyntax};
+///
+/// let hcom = cs!(hcom 0 1 (lam j => split { j = 0 => ?, i = 0 => ?cap, i = 1 => f i j }));
+/// assert!(matches!(hcom, ConcreteSyntax::HComChk(_, _, _)));
+/// ```
+///
+/// Anything other than a variable, literal or anonymous hole has to be parenthesized when it is
+/// used as an argument. Cofibrations are written 'r = s', 'r <= s', 'join(φ, ψ)', 'meet(φ, ψ)',
+/// 'top' and 'bot', and binders as 'lam x y => ...', 'let x := ... in ...', '(x y : A) -> B'
+/// and '(x : A) * B'.
+#[macro_export]
+macro_rules! cs {
+    // [NOTE: Munching splits] The branches of a split can't be matched with a plain repetition,
+    // since a 'tt' would happily eat the '=>' and ',' separators. Instead we move tokens one
+    // at a time into the cofibration and then the body of the current branch.
+    (@cof [$($acc:tt)*] []) => { vec![$($acc)*] };
+    (@cof [$($acc:tt)*] [$($cof:tt)*] => $($rest:tt)*) => {
+        $crate::cs!(@body [$($acc)*] [$($cof)*] [] $($rest)*)
+    };
+    (@cof [$($acc:tt)*] [$($cof:tt)*] $t:tt $($rest:tt)*) => {
+        $crate::cs!(@cof [$($acc)*] [$($cof)* $t] $($rest)*)
+    };
+    (@body [$($acc:tt)*] [$($cof:tt)*] [$($body:tt)*] , $($rest:tt)*) => {
+        $crate::cs!(
+            @cof [$($acc)* ($crate::cs!(@rec $($cof)*), $crate::cs!(@rec $($body)*)),]
+            [] $($rest)*
+        )
+    };
+    (@body [$($acc:tt)*] [$($cof:tt)*] [$($body:tt)*]) => {
+        vec![$($acc)* ($crate::cs!(@rec $($cof)*), $crate::cs!(@rec $($body)*))]
+    };
+    (@body [$($acc:tt)*] [$($cof:tt)*] [$($body:tt)*] $t:tt $($rest:tt)*) => {
+        $crate::cs!(@body [$($acc)*] [$($cof)*] [$($body)* $t] $($rest)*)
+    };
+    (@rec $($t:tt)+) => { $crate::syntax::SyntaxRec::new($crate::cs!($($t)+)) };
+    (@ident $x:ident) => { $crate::syntax::Ident::User(vec![stringify!($x).to_string()]) };
+
+    (?) => { $crate::syntax::ConcreteSyntax::Hole($crate::syntax::Hole { name: None, silent: false }) };
+    (? $name:ident) => {
+        $crate::syntax::ConcreteSyntax::Hole($crate::syntax::Hole {
+            name: Some(stringify!($name).to_string()),
+            silent: false,
+        })
+    };
+    (!) => { $crate::syntax::ConcreteSyntax::Hole($crate::syntax::Hole { name: None, silent: true }) };
+    (! $name:ident) => {
+        $crate::syntax::ConcreteSyntax::Hole($crate::syntax::Hole {
+            name: Some(stringify!($name).to_string()),
+            silent: true,
+        })
+    };
+    (_) => { $crate::syntax::ConcreteSyntax::Underscore };
+    (type) => { $crate::syntax::ConcreteSyntax::Type };
+    (dim) => { $crate::syntax::ConcreteSyntax::Dim };
+    (cof) => { $crate::syntax::ConcreteSyntax::Cof };
+    (top) => { $crate::syntax::ConcreteSyntax::TopC };
+    (bot) => { $crate::syntax::ConcreteSyntax::BotC };
+
+    (hcom $r:tt $s:tt $($u:tt)+) => {
+        $crate::syntax::ConcreteSyntax::HComChk(
+            $crate::cs!(@rec $r),
+            $crate::cs!(@rec $s),
+            $crate::cs!(@rec $($u)+),
+        )
+    };
+    (hfill $r:tt $($u:tt)+) => {
+        $crate::syntax::ConcreteSyntax::HFillChk(
+            $crate::cs!(@rec $r),
+            $crate::cs!(@rec $($u)+),
+        )
+    };
+    (com $a:tt $r:tt $s:tt $phi:tt $($u:tt)+) => {
+        $crate::syntax::ConcreteSyntax::Com(
+            $crate::cs!(@rec $a),
+            $crate::cs!(@rec $r),
+            $crate::cs!(@rec $s),
+            $crate::cs!(@rec $phi),
+            $crate::cs!(@rec $($u)+),
+        )
+    };
+    (coe $a:tt $r:tt $s:tt $($u:tt)+) => {
+        $crate::syntax::ConcreteSyntax::Coe(
+            $crate::cs!(@rec $a),
+            $crate::cs!(@rec $r),
+            $crate::cs!(@rec $s),
+            $crate::cs!(@rec $($u)+),
+        )
+    };
+    (fst $($t:tt)+) => { $crate::syntax::ConcreteSyntax::Fst($crate::cs!(@rec $($t)+)) };
+    (snd $($t:tt)+) => { $crate::syntax::ConcreteSyntax::Snd($crate::cs!(@rec $($t)+)) };
+    (lam $($x:ident)+ => $($body:tt)+) => {
+        $crate::syntax::ConcreteSyntax::Lam(
+            vec![$($crate::cs!(@ident $x)),+],
+            $crate::cs!(@rec $($body)+),
+        )
+    };
+    (let $x:ident := $def:tt in $($body:tt)+) => {
+        $crate::syntax::ConcreteSyntax::Let(
+            $crate::cs!(@rec $def),
+            $crate::cs!(@ident $x),
+            $crate::cs!(@rec $($body)+),
+        )
+    };
+    (split { $($branches:tt)* }) => {
+        $crate::syntax::ConcreteSyntax::CofSplit($crate::cs!(@cof [] [] $($branches)*))
+    };
+    (join($($phi:tt),*)) => {
+        $crate::syntax::ConcreteSyntax::Join(vec![$($crate::cs!(@rec $phi)),*])
+    };
+    (meet($($phi:tt),*)) => {
+        $crate::syntax::ConcreteSyntax::Meet(vec![$($crate::cs!(@rec $phi)),*])
+    };
+    (($($x:ident)+ : $($a:tt)+) -> $($b:tt)+) => {
+        $crate::syntax::ConcreteSyntax::Pi(
+            vec![$crate::syntax::Cell {
+                names: vec![$($crate::cs!(@ident $x)),+],
+                tp: $crate::cs!(@rec $($a)+),
+            }],
+            $crate::cs!(@rec $($b)+),
+        )
+    };
+    (($($x:ident)+ : $($a:tt)+) * $($b:tt)+) => {
+        $crate::syntax::ConcreteSyntax::Sg(
+            vec![$crate::syntax::Cell {
+                names: vec![$($crate::cs!(@ident $x)),+],
+                tp: $crate::cs!(@rec $($a)+),
+            }],
+            $crate::cs!(@rec $($b)+),
+        )
+    };
+    ($a:tt -> $($b:tt)+) => {
+        $crate::syntax::ConcreteSyntax::Pi(
+            vec![$crate::syntax::Cell {
+                names: vec![$crate::syntax::Ident::Anon],
+                tp: $crate::cs!(@rec $a),
+            }],
+            $crate::cs!(@rec $($b)+),
+        )
+    };
+    ($a:tt * $($b:tt)+) => {
+        $crate::syntax::ConcreteSyntax::Sg(
+            vec![$crate::syntax::Cell {
+                names: vec![$crate::syntax::Ident::Anon],
+                tp: $crate::cs!(@rec $a),
+            }],
+            $crate::cs!(@rec $($b)+),
+        )
+    };
+    ([$a:tt, $b:tt]) => {
+        $crate::syntax::ConcreteSyntax::Pair($crate::cs!(@rec $a), $crate::cs!(@rec $b))
+    };
+    ($r:tt = $s:tt) => {
+        $crate::syntax::ConcreteSyntax::CofEq($crate::cs!(@rec $r), $crate::cs!(@rec $s))
+    };
+    ($r:tt <= $s:tt) => {
+        $crate::syntax::ConcreteSyntax::CofLe($crate::cs!(@rec $r), $crate::cs!(@rec $s))
+    };
+    ($n:literal) => { $crate::syntax::ConcreteSyntax::Lit($n) };
+    ($x:ident) => { $crate::syntax::ConcreteSyntax::Var($crate::cs!(@ident $x)) };
+    (($($t:tt)+)) => { $crate::cs!($($t)+) };
+    ($f:tt $($arg:tt)+) => {
+        $crate::syntax::ConcreteSyntax::Ap(
+            $crate::cs!(@rec $f),
+            vec![$($crate::cs!(@rec $arg)),+],
+        )
+    };
+}
+
+pub use crate::cs;
+
+impl<Rec> Cell<Rec> {
+    pub fn traverse<'a, R, E, F>(&'a self, f: &mut F) -> Result<Cell<R>, E>
+    where
+        F: FnMut(&'a Rec) -> Result<R, E>,
+    {
+        Ok(Cell {
+            names: self.names.clone(),
+            tp: f(&self.tp)?,
+        })
+    }
+}
+
+// [NOTE: Traversals] Every operation that needs to look at the subterms of a node should
+// go through 'traverse' (or 'map'/'as_ref', which are defined in terms of it), so that adding a
+// new syntactic form only means touching 'traverse', the (de)serializers and the printer.
+// Traversals borrow the node and only clone the names it carries; anything that needs owned
+// subterms can get them from 'as_ref'. Subterms are always visited from left to right, which
+// is also the order that paths into terms use.
+impl<Rec> ConcreteSyntax<Rec> {
+    /// Apply a fallible function to every immediate subterm, from left to right.
+    pub fn traverse<'a, R, E, F>(&'a self, mut f: F) -> Result<ConcreteSyntax<R>, E>
+    where
+        F: FnMut(&'a Rec) -> Result<R, E>,
+    {
+        fn many<'a, Rec, R, E>(
+            vr: &'a [Rec],
+            f: &mut impl FnMut(&'a Rec) -> Result<R, E>,
+        ) -> Result<Vec<R>, E> {
+            vr.iter().map(f).collect()
+        }
+        fn pairs<'a, Rec, R, E>(
+            vp: &'a [(Rec, Rec)],
+            f: &mut impl FnMut(&'a Rec) -> Result<R, E>,
+        ) -> Result<Vec<(R, R)>, E> {
+            vp.iter().map(|(r1, r2)| Ok((f(r1)?, f(r2)?))).collect()
+        }
+        fn cells<'a, Rec, R, E>(
+            vcell: &'a [Cell<Rec>],
+            f: &mut impl FnMut(&'a Rec) -> Result<R, E>,
+        ) -> Result<Vec<Cell<R>>, E> {
+            vcell.iter().map(|cell| cell.traverse(f)).collect()
+        }
+        let cs = match self {
+            Var(id) => Var(id.clone()),
+            Let(r1, id, r2) => {
+                let r1 = f(r1)?;
+                Let(r1, id.clone(), f(r2)?)
+            }
+            Lit(n) => Lit(*n),
+            Lam(vi, r) => Lam(vi.clone(), f(r)?),
+            Ap(r, vr) => {
+                let r = f(r)?;
+                Ap(r, many(vr, &mut f)?)
+            }
+            Pi(vcell, r) => {
+                let vcell = cells(vcell, &mut f)?;
+                Pi(vcell, f(r)?)
+            }
+            Sg(vcell, r) => {
+                let vcell = cells(vcell, &mut f)?;
+                Sg(vcell, f(r)?)
+            }
+            Pair(r1, r2) => {
+                let r1 = f(r1)?;
+                Pair(r1, f(r2)?)
+            }
+            Fst(r) => Fst(f(r)?),
+            Snd(r) => Snd(f(r)?),
+            Proj(r, id) => Proj(f(r)?, id.clone()),
+            Type => Type,
+            Hole(h) => Hole(h.clone()),
+            Underscore => Underscore,
+            Dim => Dim,
+            Cof => Cof,
+            CofEq(r1, r2) => {
+                let r1 = f(r1)?;
+                CofEq(r1, f(r2)?)
+            }
+            CofLe(r1, r2) => {
+                let r1 = f(r1)?;
+                CofLe(r1, f(r2)?)
+            }
+            Join(vr) => Join(many(vr, &mut f)?),
+            Meet(vr) => Meet(many(vr, &mut f)?),
+            CofSplit(vrp) => CofSplit(pairs(vrp, &mut f)?),
+            TopC => TopC,
+            BotC => BotC,
+            HComChk(r1, r2, r3) => {
+                let r1 = f(r1)?;
+                let r2 = f(r2)?;
+                HComChk(r1, r2, f(r3)?)
+            }
+            HFillChk(r1, r2) => {
+                let r1 = f(r1)?;
+                HFillChk(r1, f(r2)?)
+            }
+            Com(r1, r2, r3, r4, r5) => {
+                let r1 = f(r1)?;
+                let r2 = f(r2)?;
+                let r3 = f(r3)?;
+                let r4 = f(r4)?;
+                Com(r1, r2, r3, r4, f(r5)?)
+            }
+            Coe(r1, r2, r3, r4) => {
+                let r1 = f(r1)?;
+                let r2 = f(r2)?;
+                let r3 = f(r3)?;
+                Coe(r1, r2, r3, f(r4)?)
+            }
+            Ext(vi, r, vrp) => {
+                let r = f(r)?;
+                Ext(vi.clone(), r, pairs(vrp, &mut f)?)
+            }
+            V(r1, r2, r3, r4) => {
+                let r1 = f(r1)?;
+                let r2 = f(r2)?;
+                let r3 = f(r3)?;
+                V(r1, r2, r3, f(r4)?)
+            }
+            VProj(r) => VProj(f(r)?),
+        };
+        Ok(cs)
+    }
+
+    /// Apply a function to every immediate subterm, from left to right.
+    pub fn map<'a, R, F>(&'a self, mut f: F) -> ConcreteSyntax<R>
+    where
+        F: FnMut(&'a Rec) -> R,
+    {
+        match self.traverse(|r| Ok::<R, Infallible>(f(r))) {
+            Ok(cs) => cs,
+            Err(void) => match void {},
+        }
+    }
+
+    pub fn as_ref(&self) -> ConcreteSyntax<&Rec> {
+        self.map(|r| r)
+    }
+
+    /// The immediate subterms of this node, from left to right, along with the names that
+    /// this node binds in each of them.
+    pub fn scoped_children(&self) -> Vec<(&Rec, Vec<Ident>)> {
+        match self {
+            Let(r1, id, r2) => vec![(r1, vec![]), (r2, vec![id.clone()])],
+            Lam(vi, r) => vec![(r, vi.clone())],
+            Pi(vcell, r) | Sg(vcell, r) => {
+                let mut bound = Vec::new();
+                let mut children = Vec::new();
+                for cell in vcell {
+                    children.push((&cell.tp, bound.clone()));
+                    bound.extend(cell.names.iter().cloned());
+                }
+                children.push((r, bound));
+                children
+            }
+            Ext(vi, _, _) => self
+                .children()
+                .into_iter()
+                .map(|r| (r, vi.clone()))
+                .collect(),
+            _ => self.children().into_iter().map(|r| (r, vec![])).collect(),
+        }
+    }
+
+    /// The names bound by this node, or 'None' if it is not a binder.
+    pub fn binders_mut(&mut self) -> Option<Vec<&mut Ident>> {
+        match self {
+            Lam(vi, _) | Ext(vi, _, _) => Some(vi.iter_mut().collect()),
+            Let(_, id, _) => Some(vec![id]),
+            Pi(vcell, _) | Sg(vcell, _) => Some(
+                vcell
+                    .iter_mut()
+                    .flat_map(|cell| cell.names.iter_mut())
+                    .collect(),
+            ),
+            _ => None,
+        }
+    }
+
+    /// The immediate subterms of this node, from left to right.
+    pub fn children(&self) -> Vec<&Rec> {
+        let mut children = Vec::new();
+        self.map(|r| children.push(r));
+        children
+    }
+}
+
 // [HACK: Avery; 2022-07-25] Yojson and Serde have different representations,
 // so we're stuck with this hellhole
 //
@@ -91,6 +479,27 @@ impl Serialize for Ident {
     }
 }
 
+impl<Rec: Serialize> Serialize for Cell<Rec> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        #[derive(Serialize)]
+        struct CellRecord<'a, Rec> {
+            names: &'a Vec<Ident>,
+            tp: &'a Rec,
+        }
+
+        let mut seq = serializer.serialize_seq(None)?;
+        seq.serialize_element("Cell")?;
+        seq.serialize_element(&CellRecord {
+            names: &self.names,
+            tp: &self.tp,
+        })?;
+        seq.end()
+    }
+}
+
 impl Node {
     fn new(map: &Rc<SlotMap<SyntaxRef, ConcreteSyntax<SyntaxRef>>>, next: &SyntaxRef) -> Node {
         Node {
@@ -105,77 +514,684 @@ impl Serialize for SerializableSyntax {
         S: Serializer,
     {
         let SerializableSyntax(map, current) = self;
+        map[*current]
+            .map(|sref| Node::new(map, sref))
+            .serialize(serializer)
+    }
+}
+
+impl Serialize for SyntaxRec {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        #[derive(Serialize)]
+        struct RecNode<'a> {
+            node: &'a ConcreteSyntax<SyntaxRec>,
+        }
+
+        RecNode { node: &self.value }.serialize(serializer)
+    }
+}
+
+impl<Rec: Serialize> Serialize for ConcreteSyntax<Rec> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
         let mut seq = serializer.serialize_seq(None)?;
-        match &map[*current] {
+        match self {
             Var(id) => {
                 seq.serialize_element("Var")?;
-                seq.serialize_element(&id)?;
+                seq.serialize_element(id)?;
+            }
+            Let(r1, id, r2) => {
+                seq.serialize_element("Let")?;
+                seq.serialize_element(r1)?;
+                seq.serialize_element(id)?;
+                seq.serialize_element(r2)?;
             }
             Lit(n) => {
                 seq.serialize_element("Lit")?;
-                seq.serialize_element(&n)?;
+                seq.serialize_element(n)?;
             }
-            Lam(vi, sref) => {
+            Lam(vi, r) => {
                 seq.serialize_element("Lam")?;
-                seq.serialize_element(&vi)?;
-                seq.serialize_element(&Node::new(map, sref))?;
+                seq.serialize_element(vi)?;
+                seq.serialize_element(r)?;
             }
-            Ap(sref, vsref) => {
+            Ap(r, vr) => {
                 seq.serialize_element("Ap")?;
-                seq.serialize_element(&Node::new(map, sref))?;
-                let vnode: Vec<Node> = vsref.iter().map(|n| Node::new(map, n)).collect();
-                seq.serialize_element(&vnode)?;
+                seq.serialize_element(r)?;
+                seq.serialize_element(vr)?;
+            }
+            Pi(vcell, r) => {
+                seq.serialize_element("Pi")?;
+                seq.serialize_element(vcell)?;
+                seq.serialize_element(r)?;
+            }
+            Sg(vcell, r) => {
+                seq.serialize_element("Sg")?;
+                seq.serialize_element(vcell)?;
+                seq.serialize_element(r)?;
+            }
+            Pair(r1, r2) => {
+                seq.serialize_element("Pair")?;
+                seq.serialize_element(r1)?;
+                seq.serialize_element(r2)?;
+            }
+            Fst(r) => {
+                seq.serialize_element("Fst")?;
+                seq.serialize_element(r)?;
+            }
+            Snd(r) => {
+                seq.serialize_element("Snd")?;
+                seq.serialize_element(r)?;
+            }
+            Proj(r, id) => {
+                seq.serialize_element("Proj")?;
+                seq.serialize_element(r)?;
+                seq.serialize_element(id)?;
             }
             Type => seq.serialize_element("Type")?,
             Hole(h) => {
                 seq.serialize_element("Hole")?;
-                seq.serialize_element(&h)?;
+                seq.serialize_element(h)?;
             }
             Underscore => seq.serialize_element("Underscore")?,
             Dim => seq.serialize_element("Dim")?,
             Cof => seq.serialize_element("Cof")?,
-            CofEq(sref1, sref2) => {
+            CofEq(r1, r2) => {
                 seq.serialize_element("CofEq")?;
-                seq.serialize_element(&Node::new(map, sref1))?;
-                seq.serialize_element(&Node::new(map, sref2))?;
+                seq.serialize_element(r1)?;
+                seq.serialize_element(r2)?;
             }
-            CofLe(sref1, sref2) => {
+            CofLe(r1, r2) => {
                 seq.serialize_element("CofLe")?;
-                seq.serialize_element(&Node::new(map, sref1))?;
-                seq.serialize_element(&Node::new(map, sref2))?;
+                seq.serialize_element(r1)?;
+                seq.serialize_element(r2)?;
             }
-            Join(vsref) => {
+            Join(vr) => {
                 seq.serialize_element("Join")?;
-                let vnode: Vec<Node> = vsref.iter().map(|n| Node::new(map, n)).collect();
-                seq.serialize_element(&vnode)?;
+                seq.serialize_element(vr)?;
             }
-            Meet(vsref) => {
+            Meet(vr) => {
                 seq.serialize_element("Meet")?;
-                let vnode: Vec<Node> = vsref.iter().map(|n| Node::new(map, n)).collect();
-                seq.serialize_element(&vnode)?;
+                seq.serialize_element(vr)?;
             }
-            CofSplit(vsrefp) => {
+            CofSplit(vrp) => {
                 seq.serialize_element("CofSplit")?;
-                let vnodep: Vec<(Node, Node)> = vsrefp
-                    .iter()
-                    .map(|(n1, n2)| (Node::new(map, n1), Node::new(map, n2)))
-                    .collect();
-                seq.serialize_element(&vnodep)?;
+                seq.serialize_element(vrp)?;
             }
             TopC => seq.serialize_element("TopC")?,
             BotC => seq.serialize_element("BotC")?,
-            HComChk(sref1, sref2, sref3) => {
+            HComChk(r1, r2, r3) => {
                 seq.serialize_element("HComChk")?;
-                seq.serialize_element(&Node::new(map, sref1))?;
-                seq.serialize_element(&Node::new(map, sref2))?;
-                seq.serialize_element(&Node::new(map, sref3))?;
+                seq.serialize_element(r1)?;
+                seq.serialize_element(r2)?;
+                seq.serialize_element(r3)?;
             }
-            HFillChk(sref1, sref2) => {
+            HFillChk(r1, r2) => {
                 seq.serialize_element("HFillChk")?;
-                seq.serialize_element(&Node::new(map, sref1))?;
-                seq.serialize_element(&Node::new(map, sref2))?;
+                seq.serialize_element(r1)?;
+                seq.serialize_element(r2)?;
+            }
+            Com(r1, r2, r3, r4, r5) => {
+                seq.serialize_element("Com")?;
+                seq.serialize_element(r1)?;
+                seq.serialize_element(r2)?;
+                seq.serialize_element(r3)?;
+                seq.serialize_element(r4)?;
+                seq.serialize_element(r5)?;
+            }
+            Coe(r1, r2, r3, r4) => {
+                seq.serialize_element("Coe")?;
+                seq.serialize_element(r1)?;
+                seq.serialize_element(r2)?;
+                seq.serialize_element(r3)?;
+                seq.serialize_element(r4)?;
+            }
+            Ext(vi, r, vrp) => {
+                seq.serialize_element("Ext")?;
+                seq.serialize_element(vi)?;
+                seq.serialize_element(r)?;
+                seq.serialize_element(vrp)?;
+            }
+            V(r1, r2, r3, r4) => {
+                seq.serialize_element("V")?;
+                seq.serialize_element(r1)?;
+                seq.serialize_element(r2)?;
+                seq.serialize_element(r3)?;
+                seq.serialize_element(r4)?;
+            }
+            VProj(r) => {
+                seq.serialize_element("VProj")?;
+                seq.serialize_element(r)?;
             }
         }
         seq.end()
     }
 }
+
+// [NOTE: Wire format] We talk to cooltt in its yojson encoding. Every variant is a list
+// whose head is the constructor name, followed by its arguments. Constructors with named
+// fields, like 'Hole', carry them as a single record, which is how ppx_yojson_conv encodes
+// inline records. Every subterm is wrapped in a `{"node": ...}` object. The deserializers
+// below mirror the serializers above.
+pub(crate) fn next_element<'de, A, T>(
+    seq: &mut A,
+    index: usize,
+    exp: &dyn Expected,
+) -> Result<T, A::Error>
+where
+    A: SeqAccess<'de>,
+    T: Deserialize<'de>,
+{
+    seq.next_element()?
+        .ok_or_else(|| de::Error::invalid_length(index, exp))
+}
+
+struct IdentVisitor;
+
+impl<'de> Visitor<'de> for IdentVisitor {
+    type Value = Ident;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("an identifier")
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Ident, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let tag: String = next_element(&mut seq, 0, &self)?;
+        match tag.as_str() {
+            "Anon" => Ok(Anon),
+            "User" => Ok(User(next_element(&mut seq, 1, &self)?)),
+            "Machine" => Ok(Machine(next_element(&mut seq, 1, &self)?)),
+            _ => Err(de::Error::unknown_variant(
+                &tag,
+                &["Anon", "User", "Machine"],
+            )),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Ident {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_seq(IdentVisitor)
+    }
+}
+
+const VARIANTS: &[&str] = &[
+    "Var",
+    "Let",
+    "Lit",
+    "Lam",
+    "Ap",
+    "Pi",
+    "Sg",
+    "Pair",
+    "Fst",
+    "Snd",
+    "Proj",
+    "Type",
+    "Hole",
+    "Underscore",
+    "Dim",
+    "Cof",
+    "CofEq",
+    "CofLe",
+    "Join",
+    "Meet",
+    "CofSplit",
+    "TopC",
+    "BotC",
+    "HComChk",
+    "HFillChk",
+    "Com",
+    "Coe",
+    "Ext",
+    "V",
+    "VProj",
+];
+
+struct CellVisitor<Rec>(PhantomData<Rec>);
+
+impl<'de, Rec: Deserialize<'de>> Visitor<'de> for CellVisitor<Rec> {
+    type Value = Cell<Rec>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a telescope cell")
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Cell<Rec>, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        #[derive(Deserialize)]
+        struct CellRecord<Rec> {
+            names: Vec<Ident>,
+            tp: Rec,
+        }
+
+        let tag: String = next_element(&mut seq, 0, &self)?;
+        if tag != "Cell" {
+            return Err(de::Error::unknown_variant(&tag, &["Cell"]));
+        }
+        let CellRecord { names, tp } = next_element(&mut seq, 1, &self)?;
+        Ok(Cell { names, tp })
+    }
+}
+
+impl<'de, Rec: Deserialize<'de>> Deserialize<'de> for Cell<Rec> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_seq(CellVisitor(PhantomData))
+    }
+}
+
+struct SyntaxVisitor<Rec>(PhantomData<Rec>);
+
+impl<'de, Rec: Deserialize<'de>> Visitor<'de> for SyntaxVisitor<Rec> {
+    type Value = ConcreteSyntax<Rec>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a concrete syntax node")
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<ConcreteSyntax<Rec>, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let tag: String = next_element(&mut seq, 0, &self)?;
+        let cs = match tag.as_str() {
+            "Var" => Var(next_element(&mut seq, 1, &self)?),
+            "Let" => Let(
+                next_element(&mut seq, 1, &self)?,
+                next_element(&mut seq, 2, &self)?,
+                next_element(&mut seq, 3, &self)?,
+            ),
+            "Lit" => Lit(next_element(&mut seq, 1, &self)?),
+            "Lam" => Lam(
+                next_element(&mut seq, 1, &self)?,
+                next_element(&mut seq, 2, &self)?,
+            ),
+            "Ap" => Ap(
+                next_element(&mut seq, 1, &self)?,
+                next_element(&mut seq, 2, &self)?,
+            ),
+            "Pi" => Pi(
+                next_element(&mut seq, 1, &self)?,
+                next_element(&mut seq, 2, &self)?,
+            ),
+            "Sg" => Sg(
+                next_element(&mut seq, 1, &self)?,
+                next_element(&mut seq, 2, &self)?,
+            ),
+            "Pair" => Pair(
+                next_element(&mut seq, 1, &self)?,
+                next_element(&mut seq, 2, &self)?,
+            ),
+            "Fst" => Fst(next_element(&mut seq, 1, &self)?),
+            "Snd" => Snd(next_element(&mut seq, 1, &self)?),
+            "Proj" => Proj(
+                next_element(&mut seq, 1, &self)?,
+                next_element(&mut seq, 2, &self)?,
+            ),
+            "Type" => Type,
+            "Hole" => Hole(next_element(&mut seq, 1, &self)?),
+            "Underscore" => Underscore,
+            "Dim" => Dim,
+            "Cof" => Cof,
+            "CofEq" => CofEq(
+                next_element(&mut seq, 1, &self)?,
+                next_element(&mut seq, 2, &self)?,
+            ),
+            "CofLe" => CofLe(
+                next_element(&mut seq, 1, &self)?,
+                next_element(&mut seq, 2, &self)?,
+            ),
+            "Join" => Join(next_element(&mut seq, 1, &self)?),
+            "Meet" => Meet(next_element(&mut seq, 1, &self)?),
+            "CofSplit" => CofSplit(next_element(&mut seq, 1, &self)?),
+            "TopC" => TopC,
+            "BotC" => BotC,
+            "HComChk" => HComChk(
+                next_element(&mut seq, 1, &self)?,
+                next_element(&mut seq, 2, &self)?,
+                next_element(&mut seq, 3, &self)?,
+            ),
+            "HFillChk" => HFillChk(
+                next_element(&mut seq, 1, &self)?,
+                next_element(&mut seq, 2, &self)?,
+            ),
+            "Com" => Com(
+                next_element(&mut seq, 1, &self)?,
+                next_element(&mut seq, 2, &self)?,
+                next_element(&mut seq, 3, &self)?,
+                next_element(&mut seq, 4, &self)?,
+                next_element(&mut seq, 5, &self)?,
+            ),
+            "Coe" => Coe(
+                next_element(&mut seq, 1, &self)?,
+                next_element(&mut seq, 2, &self)?,
+                next_element(&mut seq, 3, &self)?,
+                next_element(&mut seq, 4, &self)?,
+            ),
+            "Ext" => Ext(
+                next_element(&mut seq, 1, &self)?,
+                next_element(&mut seq, 2, &self)?,
+                next_element(&mut seq, 3, &self)?,
+            ),
+            "V" => V(
+                next_element(&mut seq, 1, &self)?,
+                next_element(&mut seq, 2, &self)?,
+                next_element(&mut seq, 3, &self)?,
+                next_element(&mut seq, 4, &self)?,
+            ),
+            "VProj" => VProj(next_element(&mut seq, 1, &self)?),
+            _ => return Err(de::Error::unknown_variant(&tag, VARIANTS)),
+        };
+        Ok(cs)
+    }
+}
+
+impl<'de, Rec: Deserialize<'de>> Deserialize<'de> for ConcreteSyntax<Rec> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_seq(SyntaxVisitor(PhantomData))
+    }
+}
+
+impl<'de> Deserialize<'de> for SyntaxRec {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        struct RecNode {
+            node: ConcreteSyntax<SyntaxRec>,
+        }
+
+        let RecNode { node } = RecNode::deserialize(deserializer)?;
+        Ok(SyntaxRec::new(node))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::termbuilder::Term;
+
+    fn rec(cs: ConcreteSyntax<SyntaxRec>) -> SyntaxRec {
+        SyntaxRec::new(cs)
+    }
+
+    fn var(name: &str) -> SyntaxRec {
+        rec(Var(User(vec![name.to_string()])))
+    }
+
+    fn hole(name: Option<&str>, silent: bool) -> SyntaxRec {
+        rec(Hole(Hole {
+            name: name.map(str::to_string),
+            silent,
+        }))
+    }
+
+    /// One of every syntactic form, and every kind of identifier.
+    fn every_variant() -> Vec<SyntaxRec> {
+        let cell = |name: Ident, tp: SyntaxRec| Cell {
+            names: vec![name],
+            tp,
+        };
+        vec![
+            var("x"),
+            rec(Var(User(vec!["M".to_string(), "x".to_string()]))),
+            rec(Var(Machine("x1".to_string()))),
+            rec(Var(Anon)),
+            rec(Let(var("a"), User(vec!["x".to_string()]), var("x"))),
+            rec(Lit(7)),
+            rec(Lam(vec![User(vec!["i".to_string()]), Anon], var("i"))),
+            rec(Ap(var("f"), vec![var("x"), rec(Lit(0))])),
+            rec(Pi(
+                vec![cell(Machine("x".to_string()), rec(Type))],
+                var("x"),
+            )),
+            rec(Sg(vec![cell(Anon, rec(Dim))], rec(Type))),
+            rec(Pair(var("a"), var("b"))),
+            rec(Fst(var("p"))),
+            rec(Snd(var("p"))),
+            rec(Proj(var("p"), User(vec!["fst".to_string()]))),
+            rec(Type),
+            hole(None, false),
+            hole(Some("goal"), true),
+            rec(Underscore),
+            rec(Dim),
+            rec(Cof),
+            rec(CofEq(var("i"), rec(Lit(0)))),
+            rec(CofLe(var("i"), var("j"))),
+            rec(Join(vec![rec(TopC), rec(BotC)])),
+            rec(Meet(vec![rec(TopC)])),
+            rec(CofSplit(vec![(rec(TopC), hole(None, false))])),
+            rec(TopC),
+            rec(BotC),
+            rec(HComChk(rec(Lit(0)), rec(Lit(1)), var("u"))),
+            rec(HFillChk(rec(Lit(1)), var("u"))),
+            rec(Com(var("A"), rec(Lit(0)), rec(Lit(1)), var("u"), var("a"))),
+            rec(Coe(var("A"), rec(Lit(0)), rec(Lit(1)), var("a"))),
+            rec(Ext(
+                vec![User(vec!["i".to_string()])],
+                var("A"),
+                vec![(rec(CofEq(var("i"), rec(Lit(0)))), var("a"))],
+            )),
+            rec(V(var("r"), var("A"), var("B"), var("e"))),
+            rec(VProj(var("v"))),
+        ]
+    }
+
+    #[test]
+    fn json_round_trip() {
+        for syntax in every_variant() {
+            let json = serde_json::to_string(&syntax).expect("Failed to serialize term");
+            let back: SyntaxRec = serde_json::from_str(&json)
+                .unwrap_or_else(|err| panic!("Failed to deserialize {}: {}", json, err));
+            assert_eq!(syntax, back, "{} did not survive a round trip", json);
+        }
+    }
+
+    #[test]
+    fn term_serializes_like_tree() {
+        for syntax in every_variant() {
+            let (term, root) = Term::from_syntax((*syntax.value).clone());
+            let node = Node {
+                node: SerializableSyntax(Rc::new(term.map), root),
+            };
+            assert_eq!(
+                serde_json::to_value(&node).expect("Failed to serialize term"),
+                serde_json::to_value(&syntax).expect("Failed to serialize term"),
+            );
+        }
+    }
+
+    #[test]
+    fn wire_format() {
+        let json = serde_json::to_value(hole(Some("goal"), true)).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({"node": ["Hole", {"name": "goal", "silent": true}]})
+        );
+        let json = serde_json::to_value(rec(Lam(vec![Anon, Machine("i1".to_string())], var("x"))))
+            .unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({"node": ["Lam", [["Anon"], ["Machine", "i1"]], {"node": ["Var", ["User", ["x"]]]}]})
+        );
+    }
+
+    #[test]
+    fn bad_tags_are_rejected() {
+        for json in [
+            r#"{"node": ["Bogus"]}"#,
+            r#"{"node": ["var", ["User", ["x"]]]}"#,
+            r#"{"node": ["Var", ["Bogus", ["x"]]]}"#,
+            r#"{"node": ["Pi", [["Bogus", {"names": [], "tp": {"node": ["Type"]}}]], {"node": ["Type"]}]}"#,
+            r#"{"node": []}"#,
+            r#"{"node": ["Lam", []]}"#,
+        ] {
+            assert!(
+                serde_json::from_str::<SyntaxRec>(json).is_err(),
+                "{} should not deserialize",
+                json
+            );
+        }
+    }
+
+    fn user(name: &str) -> Ident {
+        User(vec![name.to_string()])
+    }
+
+    #[test]
+    fn cs_holes() {
+        assert_eq!(SyntaxRec::new(cs!(?)), hole(None, false));
+        assert_eq!(SyntaxRec::new(cs!(?goal)), hole(Some("goal"), false));
+        assert_eq!(SyntaxRec::new(cs!(!)), hole(None, true));
+        assert_eq!(SyntaxRec::new(cs!(!goal)), hole(Some("goal"), true));
+        assert_eq!(SyntaxRec::new(cs!(_)), rec(Underscore));
+    }
+
+    #[test]
+    fn cs_application() {
+        assert_eq!(SyntaxRec::new(cs!(f)), var("f"));
+        assert_eq!(
+            cs!(f x 0 ?),
+            Ap(var("f"), vec![var("x"), rec(Lit(0)), hole(None, false)])
+        );
+        assert_eq!(
+            cs!(f (g x) (?h)),
+            Ap(
+                var("f"),
+                vec![rec(Ap(var("g"), vec![var("x")])), hole(Some("h"), false)]
+            )
+        );
+        assert_eq!(
+            cs!((f x) y),
+            Ap(rec(Ap(var("f"), vec![var("x")])), vec![var("y")])
+        );
+        assert_eq!(cs!(fst (f x)), Fst(rec(Ap(var("f"), vec![var("x")]))));
+        assert_eq!(
+            cs!(coe A 0 1 a),
+            Coe(var("A"), rec(Lit(0)), rec(Lit(1)), var("a"))
+        );
+    }
+
+    #[test]
+    fn cs_binders() {
+        assert_eq!(
+            cs!(lam i j => f i),
+            Lam(
+                vec![user("i"), user("j")],
+                rec(Ap(var("f"), vec![var("i")]))
+            )
+        );
+        assert_eq!(
+            cs!(let x := (f a) in g x),
+            Let(
+                rec(Ap(var("f"), vec![var("a")])),
+                user("x"),
+                rec(Ap(var("g"), vec![var("x")])),
+            )
+        );
+        assert_eq!(
+            cs!((x y : A) -> B x),
+            Pi(
+                vec![Cell {
+                    names: vec![user("x"), user("y")],
+                    tp: var("A"),
+                }],
+                rec(Ap(var("B"), vec![var("x")])),
+            )
+        );
+        assert_eq!(
+            cs!((x : type) * x),
+            Sg(
+                vec![Cell {
+                    names: vec![user("x")],
+                    tp: rec(Type),
+                }],
+                var("x"),
+            )
+        );
+        assert_eq!(
+            cs!(A -> B -> C),
+            Pi(
+                vec![Cell {
+                    names: vec![Anon],
+                    tp: var("A"),
+                }],
+                rec(Pi(
+                    vec![Cell {
+                        names: vec![Anon],
+                        tp: var("B"),
+                    }],
+                    var("C"),
+                )),
+            )
+        );
+        assert_eq!(
+            cs!(A * B),
+            Sg(
+                vec![Cell {
+                    names: vec![Anon],
+                    tp: var("A"),
+                }],
+                var("B"),
+            )
+        );
+    }
+
+    #[test]
+    fn cs_splits() {
+        assert_eq!(SyntaxRec::new(cs!(split {})), rec(CofSplit(vec![])));
+        assert_eq!(
+            cs!(split { i = 0 => ?, join((i = 1), (j <= i)) => f i, top => !done }),
+            CofSplit(vec![
+                (rec(CofEq(var("i"), rec(Lit(0)))), hole(None, false)),
+                (
+                    rec(Join(vec![
+                        rec(CofEq(var("i"), rec(Lit(1)))),
+                        rec(CofLe(var("j"), var("i"))),
+                    ])),
+                    rec(Ap(var("f"), vec![var("i")])),
+                ),
+                (rec(TopC), hole(Some("done"), true)),
+            ])
+        );
+        assert_eq!(
+            cs!(hcom 0 1 (lam j => split { j = 0 => ?cap })),
+            HComChk(
+                rec(Lit(0)),
+                rec(Lit(1)),
+                rec(Lam(
+                    vec![user("j")],
+                    rec(CofSplit(vec![(
+                        rec(CofEq(var("j"), rec(Lit(0)))),
+                        hole(Some("cap"), false),
+                    )])),
+                )),
+            )
+        );
+        assert_eq!(cs!(meet(top, bot)), Meet(vec![rec(TopC), rec(BotC)]));
+        assert_eq!(
+            cs!([a, (b c)]),
+            Pair(var("a"), rec(Ap(var("b"), vec![var("c")])))
+        );
+    }
+}
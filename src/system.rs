@@ -22,7 +22,7 @@ pub struct System {
     pub server: Server,
 }
 
-pub fn init(port: u32, title: &str) -> System {
+pub fn init(port: u16, cooltt_port: u16, title: &str) -> System {
     let title = match Path::new(&title).file_name() {
         Some(file_name) => file_name.to_str().unwrap(),
         None => title,
@@ -70,7 +70,7 @@ pub fn init(port: u32, title: &str) -> System {
 
     let renderer = Renderer::init(&mut imgui, &display).expect("Failed to initialize renderer");
 
-    let server = Server::init(port);
+    let server = Server::init(port, cooltt_port);
 
     System {
         event_loop,
@@ -86,8 +86,8 @@ pub fn init(port: u32, title: &str) -> System {
 impl System {
     pub fn main_loop<
         Scene: 'static,
-        Handle: FnMut(Message, &Display, &mut Scene, &mut Renderer) + 'static,
-        Draw: FnMut(&mut bool, &Display, &mut Scene, &mut Frame, &mut Ui) + 'static,
+        Handle: FnMut(Message, &Display, &Server, &mut Scene, &mut Renderer) + 'static,
+        Draw: FnMut(&mut bool, &Display, &Server, &mut Scene, &mut Frame, &mut Ui) + 'static,
     >(
         self,
         mut scene: Scene,
@@ -109,7 +109,7 @@ impl System {
             let gl_window = display.gl_window();
 
             if let Some(msg) = server.poll() {
-                handle_msg(msg, &display, &mut scene, &mut renderer)
+                handle_msg(msg, &display, &server, &mut scene, &mut renderer)
             }
 
             match event {
@@ -133,7 +133,14 @@ impl System {
                     let mut target = display.draw();
 
                     target.clear_color_srgb(1.0, 1.0, 1.0, 1.0);
-                    run_ui(&mut run, &display, &mut scene, &mut target, &mut ui);
+                    run_ui(
+                        &mut run,
+                        &display,
+                        &server,
+                        &mut scene,
+                        &mut target,
+                        &mut ui,
+                    );
                     if !run {
                         *control_flow = ControlFlow::Exit;
                     }
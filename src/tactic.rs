@@ -1,11 +1,378 @@
-use serde::Serialize;
+use serde::{
+    de::{self, SeqAccess, Visitor},
+    ser::SerializeSeq,
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+use std::fmt;
 
-#[derive(Debug, Serialize)]
+use crate::history::History;
+use crate::skeleton::{self, Direction};
+use crate::syntax::{
+    cs, next_element, ConcreteSyntax, ConcreteSyntax::*, Hole, Ident, SyntaxRec, SyntaxRef,
+};
+use crate::termbuilder::{HoleTarget, Term, TermError};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub enum ChkTactic {
     SilentHole(Option<String>),
     UnleashHole(Option<String>),
     UnleashSynHole(Option<String>),
+    /// Compose along a fresh line, with holes for the cap and for every boundary face of the goal.
+    Hcom(Direction),
+    /// Like 'Hcom', but produce the whole filler.
+    HFill(Direction),
+    /// Bind some variables, taking a fresh one if none are given.
+    Intro(Vec<Ident>),
+    /// Apply a function to some number of arguments, leaving a hole for each of them.
+    Apply(SynTactic, usize),
+    /// Case on some cofibrations, leaving a hole for each branch.
+    Split(Vec<SyntaxRec>),
+    /// Fill in a term, which may have holes of its own.
+    Refine(SyntaxRec),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SynTactic {
+    Var(Ident),
+    Term(SyntaxRec),
+}
+
+fn hole(name: Option<String>, silent: bool) -> ConcreteSyntax<SyntaxRec> {
+    Hole(Hole { name, silent })
+}
+
+fn anon_hole() -> SyntaxRec {
+    SyntaxRec::new(cs!(?))
+}
+
+impl SynTactic {
+    pub fn elaborate(&self) -> ConcreteSyntax<SyntaxRec> {
+        match self {
+            SynTactic::Var(id) => Var(id.clone()),
+            SynTactic::Term(tm) => (*tm.value).clone(),
+        }
+    }
+}
+
+impl ChkTactic {
+    /// The skeleton of syntax that this tactic produces, for a goal whose cube is spanned by 'dims'.
+    /// Any names it binds are taken fresh from 'term', which is where the skeleton should end up.
+    pub fn elaborate(&self, term: &mut Term, dims: &[String]) -> ConcreteSyntax<SyntaxRec> {
+        match self {
+            ChkTactic::SilentHole(name) => hole(name.clone(), true),
+            ChkTactic::UnleashHole(name) | ChkTactic::UnleashSynHole(name) => {
+                hole(name.clone(), false)
+            }
+            ChkTactic::Hcom(dir) => skeleton::hcom(term, dims, *dir),
+            ChkTactic::HFill(dir) => skeleton::hfill(term, dims, *dir),
+            ChkTactic::Intro(names) if names.is_empty() => Lam(vec![term.fresh("x")], anon_hole()),
+            ChkTactic::Intro(names) => Lam(names.clone(), anon_hole()),
+            ChkTactic::Apply(fun, arity) => Ap(
+                SyntaxRec::new(fun.elaborate()),
+                (0..*arity).map(|_| anon_hole()).collect(),
+            ),
+            ChkTactic::Split(cofs) => {
+                CofSplit(cofs.iter().map(|cof| (cof.clone(), anon_hole())).collect())
+            }
+            ChkTactic::Refine(tm) => (*tm.value).clone(),
+        }
+    }
+
+    /// Run the tactic on one of the holes of the term that 'history' is building, so that it
+    /// can be undone.
+    pub fn run(
+        &self,
+        history: &mut History,
+        target: &HoleTarget,
+        dims: &[String],
+    ) -> Result<SyntaxRef, TermError> {
+        history.plug_with(target, |term| self.elaborate(term, dims))
+    }
+}
+
+// Tactics are encoded like syntax, see [NOTE: Wire format]. Directions are given by
+// their endpoints.
+impl Serialize for ChkTactic {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut seq = serializer.serialize_seq(None)?;
+        match self {
+            ChkTactic::SilentHole(name) => {
+                seq.serialize_element("SilentHole")?;
+                seq.serialize_element(name)?;
+            }
+            ChkTactic::UnleashHole(name) => {
+                seq.serialize_element("UnleashHole")?;
+                seq.serialize_element(name)?;
+            }
+            ChkTactic::UnleashSynHole(name) => {
+                seq.serialize_element("UnleashSynHole")?;
+                seq.serialize_element(name)?;
+            }
+            ChkTactic::Hcom(dir) => {
+                let (r, s) = dir.endpoints();
+                seq.serialize_element("Hcom")?;
+                seq.serialize_element(&r)?;
+                seq.serialize_element(&s)?;
+            }
+            ChkTactic::HFill(dir) => {
+                let (r, s) = dir.endpoints();
+                seq.serialize_element("HFill")?;
+                seq.serialize_element(&r)?;
+                seq.serialize_element(&s)?;
+            }
+            ChkTactic::Intro(names) => {
+                seq.serialize_element("Intro")?;
+                seq.serialize_element(names)?;
+            }
+            ChkTactic::Apply(fun, arity) => {
+                seq.serialize_element("Apply")?;
+                seq.serialize_element(fun)?;
+                seq.serialize_element(arity)?;
+            }
+            ChkTactic::Split(cofs) => {
+                seq.serialize_element("Split")?;
+                seq.serialize_element(cofs)?;
+            }
+            ChkTactic::Refine(tm) => {
+                seq.serialize_element("Refine")?;
+                seq.serialize_element(tm)?;
+            }
+        }
+        seq.end()
+    }
+}
+
+impl Serialize for SynTactic {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut seq = serializer.serialize_seq(None)?;
+        match self {
+            SynTactic::Var(id) => {
+                seq.serialize_element("Var")?;
+                seq.serialize_element(id)?;
+            }
+            SynTactic::Term(tm) => {
+                seq.serialize_element("Term")?;
+                seq.serialize_element(tm)?;
+            }
+        }
+        seq.end()
+    }
+}
+
+struct ChkTacticVisitor;
+
+impl<'de> Visitor<'de> for ChkTacticVisitor {
+    type Value = ChkTactic;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a checking tactic")
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<ChkTactic, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let direction = |seq: &mut A| -> Result<Direction, A::Error> {
+            let r = next_element(seq, 1, &self)?;
+            let s = next_element(seq, 2, &self)?;
+            Direction::from_endpoints(r, s).ok_or_else(|| {
+                de::Error::invalid_value(
+                    de::Unexpected::Other("a pair of endpoints other than 0 and 1"),
+                    &"either 0 1 or 1 0",
+                )
+            })
+        };
+        let tag: String = next_element(&mut seq, 0, &self)?;
+        let tac = match tag.as_str() {
+            "SilentHole" => ChkTactic::SilentHole(next_element(&mut seq, 1, &self)?),
+            "UnleashHole" => ChkTactic::UnleashHole(next_element(&mut seq, 1, &self)?),
+            "UnleashSynHole" => ChkTactic::UnleashSynHole(next_element(&mut seq, 1, &self)?),
+            "Hcom" => ChkTactic::Hcom(direction(&mut seq)?),
+            "HFill" => ChkTactic::HFill(direction(&mut seq)?),
+            "Intro" => ChkTactic::Intro(next_element(&mut seq, 1, &self)?),
+            "Apply" => ChkTactic::Apply(
+                next_element(&mut seq, 1, &self)?,
+                next_element(&mut seq, 2, &self)?,
+            ),
+            "Split" => ChkTactic::Split(next_element(&mut seq, 1, &self)?),
+            "Refine" => ChkTactic::Refine(next_element(&mut seq, 1, &self)?),
+            _ => {
+                return Err(de::Error::unknown_variant(
+                    &tag,
+                    &[
+                        "SilentHole",
+                        "UnleashHole",
+                        "UnleashSynHole",
+                        "Hcom",
+                        "HFill",
+                        "Intro",
+                        "Apply",
+                        "Split",
+                        "Refine",
+                    ],
+                ))
+            }
+        };
+        Ok(tac)
+    }
 }
 
-#[derive(Debug, Serialize)]
-pub enum SynTactic {}
+impl<'de> Deserialize<'de> for ChkTactic {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_seq(ChkTacticVisitor)
+    }
+}
+
+struct SynTacticVisitor;
+
+impl<'de> Visitor<'de> for SynTacticVisitor {
+    type Value = SynTactic;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a synthesizing tactic")
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<SynTactic, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let tag: String = next_element(&mut seq, 0, &self)?;
+        match tag.as_str() {
+            "Var" => Ok(SynTactic::Var(next_element(&mut seq, 1, &self)?)),
+            "Term" => Ok(SynTactic::Term(next_element(&mut seq, 1, &self)?)),
+            _ => Err(de::Error::unknown_variant(&tag, &["Var", "Term"])),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for SynTactic {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_seq(SynTacticVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn user(name: &str) -> Ident {
+        Ident::User(vec![name.to_string()])
+    }
+
+    fn rec(cs: ConcreteSyntax<SyntaxRec>) -> SyntaxRec {
+        SyntaxRec::new(cs)
+    }
+
+    fn elaborated(tactic: ChkTactic) -> String {
+        let mut term = Term::new();
+        let dims = vec!["i".to_string()];
+        Term::from_syntax(tactic.elaborate(&mut term, &dims))
+            .0
+            .to_string()
+    }
+
+    fn print(cs: ConcreteSyntax<SyntaxRec>) -> String {
+        Term::from_syntax(cs).0.to_string()
+    }
+
+    #[test]
+    fn round_trip() {
+        for tactic in [
+            ChkTactic::SilentHole(None),
+            ChkTactic::SilentHole(Some("a".to_string())),
+            ChkTactic::UnleashHole(Some("b".to_string())),
+            ChkTactic::UnleashSynHole(None),
+            ChkTactic::Hcom(Direction::Forward),
+            ChkTactic::HFill(Direction::Backward),
+            ChkTactic::Intro(vec![]),
+            ChkTactic::Intro(vec![user("x"), Ident::Machine("y1".to_string())]),
+            ChkTactic::Apply(SynTactic::Var(user("f")), 2),
+            ChkTactic::Apply(SynTactic::Term(rec(cs!(fst p))), 0),
+            ChkTactic::Split(vec![rec(cs!(i = 0)), rec(cs!(join((i = 1), (j = 0))))]),
+            ChkTactic::Refine(rec(cs!(lam x => f x ?))),
+        ] {
+            let json = serde_json::to_string(&tactic).expect("Failed to serialize tactic");
+            let back: ChkTactic =
+                serde_json::from_str(&json).unwrap_or_else(|err| panic!("{}: {}", json, err));
+            assert_eq!(tactic, back, "{} did not survive a round trip", json);
+        }
+    }
+
+    #[test]
+    fn holes() {
+        assert_eq!(elaborated(ChkTactic::SilentHole(None)), "!");
+        assert_eq!(
+            elaborated(ChkTactic::SilentHole(Some("a".to_string()))),
+            "!a"
+        );
+        assert_eq!(
+            elaborated(ChkTactic::UnleashHole(Some("b".to_string()))),
+            "?b"
+        );
+        assert_eq!(elaborated(ChkTactic::UnleashSynHole(None)), "?");
+    }
+
+    #[test]
+    fn compositions() {
+        let dims = vec!["i".to_string()];
+        for dir in [Direction::Forward, Direction::Backward] {
+            assert_eq!(
+                elaborated(ChkTactic::Hcom(dir)),
+                print(skeleton::hcom(&mut Term::new(), &dims, dir))
+            );
+            assert_eq!(
+                elaborated(ChkTactic::HFill(dir)),
+                print(skeleton::hfill(&mut Term::new(), &dims, dir))
+            );
+        }
+    }
+
+    #[test]
+    fn intro() {
+        assert_eq!(elaborated(ChkTactic::Intro(vec![])), "λ x => ?");
+        assert_eq!(
+            elaborated(ChkTactic::Intro(vec![user("a"), user("b")])),
+            "λ a b => ?"
+        );
+    }
+
+    #[test]
+    fn apply() {
+        assert_eq!(
+            elaborated(ChkTactic::Apply(SynTactic::Var(user("f")), 2)),
+            "f ? ?"
+        );
+        assert_eq!(
+            elaborated(ChkTactic::Apply(SynTactic::Term(rec(cs!(fst p))), 1)),
+            print(cs!((fst p) ?))
+        );
+    }
+
+    #[test]
+    fn split() {
+        assert_eq!(
+            elaborated(ChkTactic::Split(vec![rec(cs!(i = 0)), rec(cs!(i = 1))])),
+            print(cs!(split { i = 0 => ?, i = 1 => ? }))
+        );
+    }
+
+    #[test]
+    fn refine() {
+        assert_eq!(
+            elaborated(ChkTactic::Refine(rec(cs!(lam x => f x ?)))),
+            print(cs!(lam x => f x ?))
+        );
+    }
+}
@@ -1,11 +1,57 @@
-use std::collections::VecDeque;
+use std::collections::{HashSet, VecDeque};
+use std::fmt;
 
-use crate::syntax::{ConcreteSyntax, ConcreteSyntax::*, Hole, SyntaxRec, SyntaxRef};
+use crate::pretty::Pretty;
+use crate::syntax::{ConcreteSyntax, Hole, Ident, SyntaxRec, SyntaxRef};
 use slotmap::SlotMap;
 
+/// A way of picking out one of the holes of a term.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum HoleTarget {
+    /// The hole at the front of the term's list of open holes.
+    Next,
+    Ref(SyntaxRef),
+    /// The first hole with this name, in the order that the holes are printed.
+    Name(String),
+    /// A path from the root, as described in 'Term::walk'.
+    Path(Vec<usize>),
+}
+
+#[derive(Clone, Debug)]
+pub enum TermError {
+    NoHoles,
+    NotAHole(SyntaxRef),
+    DanglingRef(SyntaxRef),
+    /// The reference is a hole, but not one of this term's: it was never plugged into it,
+    /// or it belongs to a subterm that has since been replaced.
+    Unreachable(SyntaxRef),
+    UnknownHole(String),
+    InvalidPath(Vec<usize>),
+}
+
+/// An open hole of a term, along with where it sits.
+#[derive(Clone, Debug)]
+pub struct HoleInfo {
+    pub sref: SyntaxRef,
+    pub hole: Hole,
+    pub path: Vec<usize>,
+    /// The names in scope at the hole, from outermost to innermost.
+    pub binders: Vec<Ident>,
+}
+
 pub struct Term {
     pub map: SlotMap<SyntaxRef, ConcreteSyntax<SyntaxRef>>,
+    root: SyntaxRef,
+    // Every open hole of the term, in the order they get printed.
     holes: VecDeque<SyntaxRef>,
+    // Names that 'fresh' must not hand out, even if they don't appear in the term (anymore).
+    reserved: HashSet<String>,
+}
+
+impl Default for Term {
+    fn default() -> Term {
+        Term::new()
+    }
 }
 
 impl Term {
@@ -18,97 +64,410 @@ impl Term {
 
         Term {
             map,
+            root: hole,
             holes: VecDeque::from([hole]),
+            reserved: HashSet::new(),
         }
     }
 
-    pub fn plug(&mut self, filler: ConcreteSyntax<SyntaxRec>) -> SyntaxRef {
-        let hole = self.holes.pop_front().expect("No holes in term!");
-        self.map[hole] = self.derecurse(filler);
-        hole
+    pub fn root(&self) -> SyntaxRef {
+        self.root
     }
 
-    fn derecurse(&mut self, filler: ConcreteSyntax<SyntaxRec>) -> ConcreteSyntax<SyntaxRef> {
-        match filler {
-            Var(id) => Var(id),
-            Lit(n) => Lit(n),
-            Lam(vi, rec) => {
-                let cs = self.derecurse(*rec.value);
-                let sref: SyntaxRef = self.map.insert(cs);
-                Lam(vi, sref)
-            }
-            Ap(rec, vrec) => {
-                let cs = self.derecurse(*rec.value);
-                let sref: SyntaxRef = self.map.insert(cs);
-                let mut vsref: Vec<SyntaxRef> = Vec::with_capacity(vrec.len());
-                for rec in vrec {
-                    let cs = self.derecurse(*rec.value);
-                    vsref.push(self.map.insert(cs))
-                }
-                Ap(sref, vsref)
-            }
-            Type => Type,
-            Hole(h) => Hole(h),
-            Underscore => Underscore,
-            Dim => Dim,
-            Cof => Cof,
-            CofEq(rec1, rec2) => {
-                let cs1 = self.derecurse(*rec1.value);
-                let sref1: SyntaxRef = self.map.insert(cs1);
-                let cs2 = self.derecurse(*rec2.value);
-                let sref2: SyntaxRef = self.map.insert(cs2);
-                CofEq(sref1, sref2)
+    pub fn pretty(&self, sref: SyntaxRef) -> Pretty<'_> {
+        Pretty::new(&self.map, sref)
+    }
+
+    /// Make sure that 'fresh' never returns any of these names, eg. because they are
+    /// variables from the context of the goal.
+    pub fn reserve<I: IntoIterator<Item = Ident>>(&mut self, ids: I) {
+        self.reserved
+            .extend(ids.into_iter().map(|id| id.to_string()));
+    }
+
+    /// The names that 'fresh' will not hand out.
+    pub fn reserved(&self) -> &HashSet<String> {
+        &self.reserved
+    }
+
+    /// Forget every reserved name that is not in 'reserved', eg. because the edit that
+    /// took it has been undone.
+    pub fn set_reserved(&mut self, reserved: HashSet<String>) {
+        self.reserved = reserved;
+    }
+
+    /// A machine name based on 'base' that doesn't clash with any name in the term,
+    /// nor with any name we have handed out before.
+    ///
+    /// Names are compared by how they print, since that is what cooltt will see: 'Machine("j1")'
+    /// clashes with 'User(["j1"])'.
+    pub fn fresh(&mut self, base: &str) -> Ident {
+        let mut taken = self.reserved.clone();
+        self.walk(self.root, &mut |_, _, cs| {
+            if let ConcreteSyntax::Var(id) = cs {
+                taken.insert(id.to_string());
             }
-            CofLe(rec1, rec2) => {
-                let cs1 = self.derecurse(*rec1.value);
-                let sref1: SyntaxRef = self.map.insert(cs1);
-                let cs2 = self.derecurse(*rec2.value);
-                let sref2: SyntaxRef = self.map.insert(cs2);
-                CofLe(sref1, sref2)
+            for (_, names) in cs.scoped_children() {
+                taken.extend(names.iter().map(|id| id.to_string()));
             }
-            Join(vrec) => {
-                let mut vsref: Vec<SyntaxRef> = Vec::with_capacity(vrec.len());
-                for rec in vrec {
-                    let cs = self.derecurse(*rec.value);
-                    vsref.push(self.map.insert(cs));
-                }
-                Join(vsref)
+        });
+        let name = std::iter::once(base.to_string())
+            .chain((1..).map(|n| format!("{}{}", base, n)))
+            .find(|name| !taken.contains(name))
+            .unwrap();
+        self.reserved.insert(name.clone());
+        Ident::Machine(name)
+    }
+
+    /// Follow a path from the root down to a subterm.
+    pub fn lookup(&self, path: &[usize]) -> Option<SyntaxRef> {
+        let mut sref = self.root;
+        for i in path {
+            sref = **self.map[sref].children().get(*i)?;
+        }
+        Some(sref)
+    }
+
+    /// The path from the root to a subterm, if it is still part of the term.
+    pub fn path_of(&self, target: SyntaxRef) -> Option<Vec<usize>> {
+        let mut found = None;
+        self.walk(self.root, &mut |path, sref, _| {
+            if sref == target {
+                found = Some(path.to_vec())
             }
-            Meet(vrec) => {
-                let mut vsref: Vec<SyntaxRef> = Vec::with_capacity(vrec.len());
-                for rec in vrec {
-                    let cs = self.derecurse(*rec.value);
-                    vsref.push(self.map.insert(cs));
-                }
-                Meet(vsref)
+        });
+        found
+    }
+
+    /// Find the hole picked out by 'target'.
+    pub fn find_hole(&self, target: &HoleTarget) -> Result<SyntaxRef, TermError> {
+        let sref = match target {
+            HoleTarget::Next => *self.holes.front().ok_or(TermError::NoHoles)?,
+            HoleTarget::Ref(sref) => *sref,
+            HoleTarget::Name(name) => {
+                let mut found = None;
+                self.walk(self.root, &mut |_, sref, cs| match cs {
+                    ConcreteSyntax::Hole(h) if found.is_none() && h.name.as_ref() == Some(name) => {
+                        found = Some(sref)
+                    }
+                    _ => (),
+                });
+                found.ok_or_else(|| TermError::UnknownHole(name.clone()))?
             }
-            CofSplit(vrecp) => {
-                let mut vsrefp: Vec<(SyntaxRef, SyntaxRef)> = Vec::with_capacity(vrecp.len());
-                for (rec1, rec2) in vrecp {
-                    let cs1 = self.derecurse(*rec1.value);
-                    let cs2 = self.derecurse(*rec2.value);
-                    vsrefp.push((self.map.insert(cs1), self.map.insert(cs2)));
-                }
-                CofSplit(vsrefp)
+            HoleTarget::Path(path) => self
+                .lookup(path)
+                .ok_or_else(|| TermError::InvalidPath(path.clone()))?,
+        };
+        match self.map.get(sref) {
+            Some(ConcreteSyntax::Hole(_)) if self.holes.contains(&sref) => Ok(sref),
+            Some(ConcreteSyntax::Hole(_)) => Err(TermError::Unreachable(sref)),
+            Some(_) => Err(TermError::NotAHole(sref)),
+            None => Err(TermError::DanglingRef(sref)),
+        }
+    }
+
+    /// A term made up of 'cs', along with the node that holds it.
+    pub fn from_syntax(cs: ConcreteSyntax<SyntaxRec>) -> (Term, SyntaxRef) {
+        let mut term = Term::new();
+        let sref = term.plug(cs).expect("A fresh term has a hole");
+        (term, sref)
+    }
+
+    /// Fill the next open hole.
+    pub fn plug(&mut self, filler: ConcreteSyntax<SyntaxRec>) -> Result<SyntaxRef, TermError> {
+        self.plug_at(&HoleTarget::Next, filler)
+    }
+
+    /// Fill a particular hole, returning the reference to the filled node.
+    pub fn plug_at(
+        &mut self,
+        target: &HoleTarget,
+        filler: ConcreteSyntax<SyntaxRec>,
+    ) -> Result<SyntaxRef, TermError> {
+        let hole = self.find_hole(target)?;
+        let idx = self
+            .holes
+            .iter()
+            .position(|sref| *sref == hole)
+            .ok_or(TermError::Unreachable(hole))?;
+        self.map[hole] = self.derecurse(&filler);
+
+        // The filler may well have holes of its own, which take the place of the one we just filled.
+        let mut new_holes = Vec::new();
+        self.walk(hole, &mut |_, sref, cs| {
+            if let ConcreteSyntax::Hole(_) = cs {
+                new_holes.push(sref)
             }
-            TopC => TopC,
-            BotC => BotC,
-            HComChk(rec1, rec2, rec3) => {
-                let cs1 = self.derecurse(*rec1.value);
-                let sref1: SyntaxRef = self.map.insert(cs1);
-                let cs2 = self.derecurse(*rec2.value);
-                let sref2: SyntaxRef = self.map.insert(cs2);
-                let cs3 = self.derecurse(*rec3.value);
-                let sref3: SyntaxRef = self.map.insert(cs3);
-                HComChk(sref1, sref2, sref3)
+        });
+        self.holes.remove(idx);
+        for (i, sref) in new_holes.into_iter().enumerate() {
+            self.holes.insert(idx + i, sref);
+        }
+        Ok(hole)
+    }
+
+    /// Turn a subterm back into an anonymous hole.
+    pub fn unplug(&mut self, sref: SyntaxRef) -> Result<(), TermError> {
+        self.replace(
+            sref,
+            ConcreteSyntax::Hole(Hole {
+                name: None,
+                silent: false,
+            }),
+        )
+        .map(|_| ())
+    }
+
+    /// Overwrite a subterm with 'filler', which may contain holes of its own.
+    /// Everything below the old subterm is freed.
+    pub fn replace(
+        &mut self,
+        sref: SyntaxRef,
+        filler: ConcreteSyntax<SyntaxRec>,
+    ) -> Result<SyntaxRef, TermError> {
+        if !self.map.contains_key(sref) {
+            return Err(TermError::DanglingRef(sref));
+        }
+        self.free_descendants(sref);
+        self.map[sref] = self.derecurse(&filler);
+        self.reindex();
+        Ok(sref)
+    }
+
+    fn free_descendants(&mut self, sref: SyntaxRef) {
+        let mut dead = Vec::new();
+        self.walk(sref, &mut |path, child, _| {
+            if !path.is_empty() {
+                dead.push(child)
             }
-            HFillChk(rec1, rec2) => {
-                let cs1 = self.derecurse(*rec1.value);
-                let sref1: SyntaxRef = self.map.insert(cs1);
-                let cs2 = self.derecurse(*rec2.value);
-                let sref2: SyntaxRef = self.map.insert(cs2);
-                HFillChk(sref1, sref2)
+        });
+        for child in dead {
+            self.map.remove(child);
+        }
+    }
+
+    // Rebuild the list of holes from scratch, for when an edit might have removed some.
+    fn reindex(&mut self) {
+        let mut holes = VecDeque::new();
+        self.walk(self.root, &mut |_, sref, cs| {
+            if let ConcreteSyntax::Hole(_) = cs {
+                holes.push_back(sref)
             }
+        });
+        self.holes = holes;
+    }
+
+    /// Free every node that can no longer be reached from the root, returning how many there were.
+    /// 'unplug' and 'replace' clean up after themselves, so this is only needed if nodes
+    /// were inserted into the map by hand.
+    pub fn collect_garbage(&mut self) -> usize {
+        let mut live = HashSet::new();
+        self.walk(self.root, &mut |_, sref, _| {
+            live.insert(sref);
+        });
+        let before = self.map.len();
+        self.map.retain(|sref, _| live.contains(&sref));
+        self.reindex();
+        before - self.map.len()
+    }
+
+    /// All the open holes of the term, in the order they get printed.
+    pub fn holes(&self) -> Vec<HoleInfo> {
+        let mut info = Vec::new();
+        self.holes_under(self.root, &mut Vec::new(), &mut Vec::new(), &mut info);
+        info
+    }
+
+    fn holes_under(
+        &self,
+        sref: SyntaxRef,
+        path: &mut Vec<usize>,
+        binders: &mut Vec<Ident>,
+        info: &mut Vec<HoleInfo>,
+    ) {
+        let cs = &self.map[sref];
+        if let ConcreteSyntax::Hole(hole) = cs {
+            info.push(HoleInfo {
+                sref,
+                hole: hole.clone(),
+                path: path.clone(),
+                binders: binders.clone(),
+            });
         }
+        for (i, (child, bound)) in cs.scoped_children().into_iter().enumerate() {
+            let depth = binders.len();
+            path.push(i);
+            binders.extend(bound);
+            self.holes_under(*child, path, binders, info);
+            binders.truncate(depth);
+            path.pop();
+        }
+    }
+
+    fn derecurse(&mut self, filler: &ConcreteSyntax<SyntaxRec>) -> ConcreteSyntax<SyntaxRef> {
+        filler.map(|rec| {
+            let cs = self.derecurse(&rec.value);
+            self.map.insert(cs)
+        })
+    }
+
+    /// The inverse of 'derecurse': pull a subterm out of the map as an owned syntax tree.
+    pub fn rerecurse(&self, sref: SyntaxRef) -> ConcreteSyntax<SyntaxRec> {
+        self.map[sref].map(|child| SyntaxRec::new(self.rerecurse(*child)))
+    }
+
+    /// Copy a subterm of 'source' into one of our holes.
+    pub fn paste(
+        &mut self,
+        target: &HoleTarget,
+        source: &Term,
+        sref: SyntaxRef,
+    ) -> Result<SyntaxRef, TermError> {
+        if !source.map.contains_key(sref) {
+            return Err(TermError::DanglingRef(sref));
+        }
+        self.plug_at(target, source.rerecurse(sref))
+    }
+
+    /// Fold over a subterm from the bottom up. The function gets the folded subterms
+    /// of each node in place of its children.
+    pub fn fold<A, F>(&self, sref: SyntaxRef, f: &mut F) -> A
+    where
+        F: FnMut(SyntaxRef, ConcreteSyntax<A>) -> A,
+    {
+        let cs = self.map[sref].map(|child| self.fold(*child, f));
+        f(sref, cs)
+    }
+
+    /// Visit every node of a subterm in preorder, along with its path from 'sref'.
+    /// A path lists the index of the child taken at each step, as ordered by 'ConcreteSyntax::children'.
+    pub fn walk<F>(&self, sref: SyntaxRef, f: &mut F)
+    where
+        F: FnMut(&[usize], SyntaxRef, &ConcreteSyntax<SyntaxRef>),
+    {
+        let mut path = Vec::new();
+        self.walk_path(&mut path, sref, f);
+    }
+
+    fn walk_path<F>(&self, path: &mut Vec<usize>, sref: SyntaxRef, f: &mut F)
+    where
+        F: FnMut(&[usize], SyntaxRef, &ConcreteSyntax<SyntaxRef>),
+    {
+        let cs = &self.map[sref];
+        f(path, sref, cs);
+        for (i, child) in cs.children().into_iter().enumerate() {
+            path.push(i);
+            self.walk_path(path, *child, f);
+            path.pop();
+        }
+    }
+}
+
+impl fmt::Display for Term {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.pretty(self.root))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::syntax::cs;
+
+    #[test]
+    fn plugging_detached_holes() {
+        let (mut term, _) = Term::from_syntax(cs!(f??));
+        let stray = term.map.insert(ConcreteSyntax::Hole(Hole {
+            name: None,
+            silent: false,
+        }));
+        assert!(matches!(
+            term.plug_at(&HoleTarget::Ref(stray), cs!(x)),
+            Err(TermError::Unreachable(sref)) if sref == stray
+        ));
+        assert_eq!(term.to_string(), "f ? ?");
+        assert_eq!(term.holes().len(), 2);
+    }
+
+    #[test]
+    fn plugging_replaced_holes() {
+        let (mut term, app) = Term::from_syntax(cs!(f??));
+        let first = term.holes()[0].sref;
+        term.replace(app, cs!(g?)).expect("Failed to replace term");
+        assert!(matches!(
+            term.plug_at(&HoleTarget::Ref(first), cs!(x)),
+            Err(TermError::DanglingRef(_))
+        ));
+        let hole = term.holes()[0].sref;
+        term.plug_at(&HoleTarget::Ref(hole), cs!(x))
+            .expect("Failed to plug term");
+        assert_eq!(term.to_string(), "g x");
+        assert!(term.holes().is_empty());
+    }
+
+    #[test]
+    fn fresh_names() {
+        let machine = |name: &str| Ident::Machine(name.to_string());
+
+        let mut term = Term::new();
+        assert_eq!(term.fresh("j"), machine("j"));
+        assert_eq!(term.fresh("j"), machine("j1"));
+        assert_eq!(term.fresh("j"), machine("j2"));
+
+        let (mut term, _) = Term::from_syntax(cs!(lam j => ?));
+        assert_eq!(term.fresh("j"), machine("j1"));
+
+        let mut term = Term::new();
+        term.reserve([Ident::User(vec!["j".to_string()])]);
+        assert_eq!(term.fresh("j"), machine("j1"));
+        term.set_reserved(HashSet::from(["j".to_string(), "j1".to_string()]));
+        assert_eq!(term.fresh("j"), machine("j2"));
+    }
+
+    #[test]
+    fn freeing_subterms() {
+        let (mut term, app) = Term::from_syntax(cs!(f (g a b) c));
+        assert_eq!(term.map.len(), 7);
+        let inner = term.lookup(&[1]).expect("Missing argument");
+        term.unplug(inner).expect("Failed to unplug term");
+        assert_eq!(term.to_string(), "f ? c");
+        assert_eq!(term.map.len(), 4);
+
+        term.replace(app, cs!(h x)).expect("Failed to replace term");
+        assert_eq!(term.to_string(), "h x");
+        assert_eq!(term.map.len(), 3);
+        assert_eq!(term.collect_garbage(), 0);
+    }
+
+    #[test]
+    fn collecting_garbage() {
+        let (mut term, _) = Term::from_syntax(cs!(f?));
+        let before = term.map.len();
+        let stray = term.map.insert(ConcreteSyntax::Underscore);
+        term.map.insert(ConcreteSyntax::Ap(stray, vec![stray]));
+        assert_eq!(term.collect_garbage(), 2);
+        assert_eq!(term.map.len(), before);
+        assert_eq!(term.holes().len(), 1);
+    }
+
+    #[test]
+    fn hole_paths_and_binders() {
+        let (term, _) = Term::from_syntax(cs!(lam i => split { i = 0 => ?a, i = 1 => !b }));
+        let i = Ident::User(vec!["i".to_string()]);
+        let holes: Vec<(String, Vec<usize>, Vec<Ident>)> = term
+            .holes()
+            .into_iter()
+            .map(|info| {
+                assert_eq!(term.lookup(&info.path), Some(info.sref));
+                (info.hole.to_string(), info.path, info.binders)
+            })
+            .collect();
+        assert_eq!(
+            holes,
+            vec![
+                ("?a".to_string(), vec![0, 1], vec![i.clone()]),
+                ("!b".to_string(), vec![0, 3], vec![i]),
+            ]
+        );
     }
 }